@@ -1,18 +1,16 @@
-use std::str::FromStr;
 use radix_common::address::AddressBech32Decoder;
-use radix_common::network::NetworkDefinition;
 use radix_common::types::ComponentAddress;
 use radix_common_derive::dec;
 use radix_transactions::manifest::decompile;
 use radix_transactions::model::SubintentManifestV2;
-use anthic_client::AnthicClient;
+use anthic_client::{AnthicClient, AnthicEnvironment};
 use anthic_model::{AnthicAddressInfo, AnthicConfig};
 use anthic_subintents::*;
 
 #[tokio::main]
 async fn main() {
-    let network = NetworkDefinition::from_str("stokenet").unwrap();
-    let trade_api_url = "https://trade-api.staging.anthic.io";
+    let environment = AnthicEnvironment::Stokenet;
+    let network = environment.network();
     let anthic_api_key = "<YOUR ANTHIC-API-KEY>";
     let user_address = {
         let decoder = AddressBech32Decoder::new(&network);
@@ -20,11 +18,10 @@ async fn main() {
     };
 
     // A high level Anthic client which wraps calls to the Anthic API
-    let client = AnthicClient::new(
-        network.clone(),
-        trade_api_url.to_string(),
-        anthic_api_key.to_string()
-    );
+    let client = AnthicClient::for_environment(&environment, anthic_api_key.to_string());
+
+    // Ensure the api serves addresses for the selected network
+    client.check_environment().await.unwrap();
 
     // Anthic configuration
     let anthic_config = client.load_anthic_config().await.unwrap();
@@ -1,25 +1,27 @@
-use std::str::FromStr;
-use radix_common::network::NetworkDefinition;
-use anthic_client::AnthicClient;
-use anthic_model::TokenIdentifierOnChain;
+use radix_common::prelude::*;
+use radix_common_derive::dec;
+use radix_transactions::manifest::decompile;
+use anthic_client::{AnthicClient, AnthicEnvironment};
+use anthic_model::{Chain, TokenIdentifierOnChain};
+use anthic_subintents::repay::{InstamintRepaymentManifestBuilder, RepaymentAmount};
 
 #[tokio::main]
 async fn main() {
-    let network = NetworkDefinition::from_str("stokenet").unwrap();
-    let trade_api_url = "https://trade-api.staging.anthic.io";
+    let environment = AnthicEnvironment::Stokenet;
     let anthic_api_key = "<YOUR ANTHIC-API-KEY>";
     let symbol = "xUSDT";
-    let payback_chain = "Radix";
+    let payback_chain = Chain::Radix;
+    let account_address = "<YOUR ACCOUNT ADDRESS>";
 
     // A high level Anthic client which wraps calls to the Anthic API
-    let client = AnthicClient::new(
-        network.clone(),
-        trade_api_url.to_string(),
-        anthic_api_key.to_string()
-    );
+    let client = AnthicClient::for_environment(&environment, anthic_api_key.to_string());
+
+    // Ensure the api serves addresses for the selected network
+    client.check_environment().await.unwrap();
 
     // Get repayment info for your account
     let repayment_info = client.load_instamint_payback_addresses().await.map_err(|e| println!("{:#?}", e)).unwrap();
+    let account = ComponentAddress::try_from_bech32(&client.decoder, account_address).unwrap();
 
     // Get all outstanding instamint loans
     let outstanding_loans = client.get_instamint_balance().await.unwrap();
@@ -28,7 +30,7 @@ async fn main() {
     let outstanding_xusdt_loan = outstanding_loans.get(symbol).cloned().unwrap_or_default();
 
     // Get the repayment address info for paying back xUSDT
-    let repayment_address = repayment_info.get_repayment_address(symbol, payback_chain).unwrap();
+    let repayment_address = repayment_info.get_repayment_address(symbol, &payback_chain).unwrap();
 
     println!("Loan: {} {}", outstanding_xusdt_loan, symbol);
     println!("Payback Chain: {}", repayment_address.chain);
@@ -37,4 +39,12 @@ async fn main() {
         TokenIdentifierOnChain::Address(address) => address,
         TokenIdentifierOnChain::Native => "Native Token".to_string(),
     });
-}
\ No newline at end of file
+
+    // Build a transaction manifest repaying the entire xUSDT loan, to be signed and submitted by your wallet
+    let (manifest, _) = InstamintRepaymentManifestBuilder::new(&client.network, repayment_info, outstanding_loans)
+        .lock_fee(dec!(10))
+        .repay(symbol, RepaymentAmount::Full)
+        .build(account)
+        .unwrap();
+    println!("{}", decompile(&manifest, &client.network).unwrap());
+}
@@ -0,0 +1,53 @@
+use std::fs;
+use std::path::Path;
+use radix_common::prelude::*;
+use radix_transactions::model::SignatureWithPublicKeyV1;
+use serde::Deserialize;
+
+/// A JSON keystore file, e.g. `{ "curve": "secp256k1", "private_key_hex": "..." }`
+#[derive(Deserialize)]
+struct KeystoreFile {
+    curve: KeystoreCurve,
+    private_key_hex: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum KeystoreCurve {
+    Secp256k1,
+    Ed25519,
+}
+
+pub enum Keystore {
+    Secp256k1(Secp256k1PrivateKey),
+    Ed25519(Ed25519PrivateKey),
+}
+
+impl Keystore {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| format!("Cannot read {}: {}", path.display(), err))?;
+        let file: KeystoreFile = serde_json::from_str(&contents).map_err(|err| format!("Invalid keystore: {}", err))?;
+        let bytes = hex::decode(file.private_key_hex.trim()).map_err(|err| format!("Invalid private key hex: {}", err))?;
+
+        match file.curve {
+            KeystoreCurve::Secp256k1 => Secp256k1PrivateKey::from_bytes(&bytes)
+                .map(Keystore::Secp256k1)
+                .map_err(|_| "Invalid Secp256k1 private key".to_string()),
+            KeystoreCurve::Ed25519 => Ed25519PrivateKey::from_bytes(&bytes)
+                .map(Keystore::Ed25519)
+                .map_err(|_| "Invalid Ed25519 private key".to_string()),
+        }
+    }
+
+    pub fn sign(&self, hash: &impl IsHash) -> SignatureWithPublicKeyV1 {
+        match self {
+            Keystore::Secp256k1(key) => SignatureWithPublicKeyV1::Secp256k1 {
+                signature: key.sign(hash),
+            },
+            Keystore::Ed25519(key) => SignatureWithPublicKeyV1::Ed25519 {
+                public_key: key.public_key(),
+                signature: key.sign(hash),
+            },
+        }
+    }
+}
@@ -0,0 +1,591 @@
+mod keystore;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use radix_common::prelude::*;
+use radix_transactions::manifest::decompile;
+use radix_transactions::model::*;
+use radix_transactions::prelude::*;
+use serde_json::json;
+use anthic_client::{AnthicClient, AnthicEnvironment};
+use anthic_model::snapshot::{AnthicConfigSnapshot, Snapshot};
+use anthic_model::TokenIdentifierOnChain;
+use anthic_subintents::assemble::{create_signed_partial_transaction, SubintentAssembler};
+use anthic_subintents::inspect::inspect_hex;
+use anthic_subintents::messages::client_order_id_message;
+use anthic_subintents::nonce::{NonceProvider, PersistedCounterNonce, RandomNonce};
+use anthic_subintents::render::OrderRenderer;
+use anthic_subintents::validate::anthic_validate_subintent;
+use anthic_subintents::{AnthicSubintentManifestBuilder, BuyConstraint, LimitOrderOptions, OrderLeg, TokenAmount};
+use anthic_trade_api_client::model::SubmitOrderResponse;
+use anthic_trade_api_client::transport::TradeApiTransport;
+use crate::keystore::Keystore;
+
+#[derive(Parser)]
+#[command(name = "anthic", version, about = "Command line tool for Anthic")]
+struct Cli {
+    /// mainnet, stokenet or staging
+    #[arg(long, global = true, default_value = "stokenet")]
+    environment: AnthicEnvironment,
+    /// Overrides the trade api url of the environment
+    #[arg(long, global = true)]
+    url: Option<String>,
+    /// Radix network of a custom --url, mainnet or stokenet. Defaults to the network of the environment.
+    #[arg(long, global = true, requires = "url")]
+    network: Option<String>,
+    /// Required by submit and the instamint commands
+    #[arg(long, global = true, env = "ANTHIC_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show tokens and fees
+    Config,
+    /// Show the tokens traded on Anthic
+    Tokens,
+    /// Show the token pairs traded on Anthic
+    Pairs,
+    /// Show settlement fees and Anthic fees per level
+    Fees,
+    /// Show the fee level of an account
+    AddressLevel {
+        account: String,
+    },
+    /// Build a limit order subintent manifest and print it decompiled
+    ///
+    /// Orders are taker orders. Fees are paid in the sell token on top of the sell amount: the
+    /// settlement fee of the sell token plus the taker fee of the account's fee level.
+    BuildOrder(OrderArgs),
+    /// Build a limit order subintent and sign it with a keystore
+    ///
+    /// Fees are paid as for build-order.
+    Sign {
+        #[command(flatten)]
+        order: OrderArgs,
+        /// JSON file of the form { "curve": "secp256k1", "private_key_hex": "..." }
+        #[arg(long)]
+        keystore: PathBuf,
+        /// Anthic requires a minimum of 10 seconds expiry
+        #[arg(long, default_value_t = 15)]
+        expire_after_secs: i64,
+        /// File holding a nonce counter, guaranteeing unique nonces across runs. Nonces are random otherwise.
+        #[arg(long)]
+        nonce_file: Option<PathBuf>,
+        /// Attached to the subintent as a plaintext message, to correlate the order with your own systems
+        #[arg(long)]
+        client_order_id: Option<String>,
+    },
+    /// Decode a hex encoded signed partial transaction or subintent and report its contents
+    Inspect {
+        hex: String,
+    },
+    /// Validate a hex encoded signed partial transaction
+    Validate {
+        signed_partial_transaction_hex: String,
+    },
+    /// Submit a hex encoded signed partial transaction to Anthic, printing the response as JSON
+    Submit {
+        signed_partial_transaction_hex: String,
+    },
+    #[command(subcommand)]
+    Instamint(InstamintCommand),
+}
+
+impl Command {
+    fn requires_api_key(&self) -> bool {
+        matches!(self, Command::Submit { .. } | Command::Instamint(_))
+    }
+}
+
+#[derive(Subcommand)]
+enum InstamintCommand {
+    /// Show outstanding instamint loans
+    Balance,
+    /// Show where to repay instamint loans
+    PaybackAddresses,
+}
+
+#[derive(Args)]
+struct OrderArgs {
+    /// The account placing the order
+    #[arg(long)]
+    account: String,
+    /// Symbol of the token to sell
+    #[arg(long)]
+    sell: String,
+    #[arg(long, value_parser = parse_decimal)]
+    sell_amount: Decimal,
+    /// Symbol of the token to buy
+    #[arg(long)]
+    buy: String,
+    /// The minimum amount to receive, or the exact amount with --exact
+    #[arg(long, value_parser = parse_decimal)]
+    buy_amount: Decimal,
+    /// Receive exactly the buy amount instead of at least it
+    #[arg(long, conflicts_with = "buy_max_amount")]
+    exact: bool,
+    /// Receive between the buy amount and this amount, inclusive
+    #[arg(long, value_parser = parse_decimal)]
+    buy_max_amount: Option<Decimal>,
+}
+
+impl OrderArgs {
+    fn options(&self) -> LimitOrderOptions {
+        let buy_constraint = match self.buy_max_amount {
+            Some(max) => BuyConstraint::AmountRange { max },
+            None if self.exact => BuyConstraint::Exact,
+            None => BuyConstraint::AtLeast,
+        };
+        LimitOrderOptions {
+            buy_constraint,
+            fee_symbol: None,
+        }
+    }
+}
+
+struct Output {
+    json: bool,
+}
+
+impl Output {
+    fn print(&self, value: serde_json::Value, text: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        } else {
+            println!("{}", text());
+        }
+        Ok(())
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(err) = run(cli).await {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    if cli.command.requires_api_key() && cli.api_key.as_deref().map_or(true, str::is_empty) {
+        return Err("This command needs an api key, pass --api-key or set ANTHIC_API_KEY".into());
+    }
+    let environment = match cli.url {
+        Some(trade_api_url) => AnthicEnvironment::Custom {
+            network: match cli.network.as_deref() {
+                Some("mainnet") => NetworkDefinition::mainnet(),
+                Some("stokenet") => NetworkDefinition::stokenet(),
+                Some(network) => return Err(format!("Unknown network: {}", network).into()),
+                None => cli.environment.network(),
+            },
+            trade_api_url,
+        },
+        None => cli.environment,
+    };
+    let network = environment.network();
+    let client = AnthicClient::for_environment(&environment, cli.api_key.unwrap_or_default());
+    let output = Output { json: cli.json };
+
+    match cli.command {
+        Command::Config => {
+            let config = client.load_anthic_config().await?.to_snapshot(&network)?;
+            output.print(serde_json::to_value(&config)?, || {
+                let mut lines = vec!["Tokens:".to_string()];
+                lines.extend(config.symbol_to_resource.iter().map(|(symbol, address)| format!("  {:<16} {}", symbol, address)));
+                lines.extend(fee_lines(&config));
+                lines.join("\n")
+            })?;
+        }
+        Command::Tokens => {
+            let tokens = client.load_token_registry().await?;
+            let tokens = tokens.iter()
+                .map(|token| {
+                    let address = client.encoder.encode(token.address.as_bytes())
+                        .map_err(|err| format!("Encode error for {}: {:?}", token.symbol, err))?;
+                    Ok((token, address))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let value: Vec<_> = tokens.iter()
+                .map(|(token, address)| json!({
+                    "symbol": token.symbol,
+                    "address": address,
+                    "divisibility": token.divisibility,
+                    "name": token.name,
+                    "icon_url": token.icon_url,
+                }))
+                .collect();
+            output.print(json!(value), || {
+                tokens.iter()
+                    .map(|(token, address)| {
+                        let divisibility = token.divisibility.map(|divisibility| divisibility.to_string()).unwrap_or_else(|| "unknown".to_string());
+                        format!("{:<16} {} (divisibility {})", token.symbol, address, divisibility)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })?;
+        }
+        Command::Pairs => {
+            let pairs = client.trade_api_client.token_pairs().await?.token_pairs;
+            output.print(serde_json::to_value(&pairs)?, || {
+                pairs.iter().map(|pair| format!("{}/{}", pair.base, pair.quote)).collect::<Vec<_>>().join("\n")
+            })?;
+        }
+        Command::Fees => {
+            let config = client.load_anthic_config().await?.to_snapshot(&network)?;
+            let fees = json!({
+                "settlement_fee_per_resource": config.settlement_fee_per_resource,
+                "anthic_fee_per_level": config.anthic_fee_per_level,
+            });
+            output.print(fees, || fee_lines(&config).join("\n"))?;
+        }
+        Command::AddressLevel { account } => {
+            let address = decode_account(&client, &account)?;
+            let info = client.load_account_address_info(address).await?;
+            output.print(json!({ "account": account, "level": info.level }), || format!("Level: {}", info.level))?;
+        }
+        Command::BuildOrder(order) => {
+            let manifest = build_order_manifest(&client, &order).await?;
+            let decompiled = decompile(&manifest, &network).map_err(|err| format!("Decompile error: {:?}", err))?;
+            output.print(json!({ "manifest": decompiled }), || decompiled.clone())?;
+        }
+        Command::Sign { order, keystore, expire_after_secs, nonce_file, client_order_id } => {
+            let keystore = Keystore::load(&keystore)?;
+            let manifest = build_order_manifest(&client, &order).await?;
+            let cur_epoch = client.trade_api_client.network_status().await?.cur_epoch;
+            let nonces: Box<dyn NonceProvider> = match nonce_file {
+                Some(path) => Box::new(PersistedCounterNonce::open(path)?),
+                None => Box::new(RandomNonce),
+            };
+            let message = match &client_order_id {
+                Some(id) => client_order_id_message(id),
+                None => MessageV2::None,
+            };
+            let (subintent, hash) = SubintentAssembler::new(network.clone(), nonces)
+                .assemble_with_message(manifest, expire_after_secs, cur_epoch, message)?;
+
+            let signature = keystore.sign(&hash);
+            let signed_partial_transaction = create_signed_partial_transaction(subintent, vec![signature]);
+
+            let signed_partial_transaction_hex = signed_partial_transaction.to_raw()
+                .map_err(|err| format!("Encode error: {:?}", err))?
+                .to_hex();
+            let subintent_hash = TransactionHashBech32Encoder::new(&network).encode(&hash)
+                .map_err(|err| format!("Encode error: {:?}", err))?;
+            output.print(
+                json!({
+                    "subintent_hash": subintent_hash,
+                    "signed_partial_transaction_hex": signed_partial_transaction_hex,
+                }),
+                || format!("Subintent hash: {}\nSigned partial transaction: {}", subintent_hash, signed_partial_transaction_hex),
+            )?;
+        }
+        Command::Inspect { hex } => {
+            let inspection = inspect_hex(&hex, &network)?;
+            output.print(inspection.to_json()?, || {
+                let header = &inspection.header;
+                let mut lines = vec![
+                    format!("Subintent hash: {}", TransactionHashBech32Encoder::new(&inspection.network).encode(&inspection.subintent_hash).unwrap_or_default()),
+                    format!("Network: {} ({})", inspection.network.logical_name, header.network_id),
+                    format!("Epochs: {}..{}", header.start_epoch_inclusive, header.end_epoch_exclusive),
+                    format!("Expiry: {:?}", header.max_proposer_timestamp_exclusive),
+                    format!("Nonce: {}", header.nonce),
+                ];
+                if let Some(mismatch) = &inspection.network_mismatch {
+                    lines.push(format!("Warning: {}", mismatch));
+                }
+                lines.extend(inspection.signers.iter().map(|signer| {
+                    let public_key = signer.public_key.map(|key| hex::encode(key.to_vec())).unwrap_or_else(|| "unknown".to_string());
+                    format!("Signer: {} ({})", public_key, if signer.valid { "valid" } else { "invalid" })
+                }));
+                match &inspection.validation {
+                    Ok(_) => lines.push("Validation: ok".to_string()),
+                    Err(error) => lines.push(format!("Validation: {}", error)),
+                }
+                match &inspection.manifest {
+                    Ok(manifest) => lines.push(manifest.clone()),
+                    Err(error) => lines.push(error.clone()),
+                }
+                lines.join("\n")
+            })?;
+        }
+        Command::Validate { signed_partial_transaction_hex } => {
+            let signed_partial_transaction = decode_signed_partial_transaction(&signed_partial_transaction_hex)?;
+            let order = anthic_validate_subintent(&signed_partial_transaction.partial_transaction.root_subintent)?;
+            let config = client.load_anthic_config().await?;
+            let renderer = OrderRenderer::new(&config, &network);
+            output.print(renderer.to_json(&order), || renderer.summary(&order))?;
+        }
+        Command::Submit { signed_partial_transaction_hex } => {
+            let response = submit_order(&client, &signed_partial_transaction_hex).await?;
+            println!("{}", serde_json::to_string_pretty(&response)?);
+        }
+        Command::Instamint(InstamintCommand::Balance) => {
+            let balances: BTreeMap<String, Decimal> = client.get_instamint_balance().await?.into_iter().collect();
+            let value: BTreeMap<&String, String> = balances.iter().map(|(symbol, amount)| (symbol, amount.to_string())).collect();
+            output.print(json!(value), || {
+                balances.iter().map(|(symbol, amount)| format!("{:<16} {}", symbol, amount)).collect::<Vec<_>>().join("\n")
+            })?;
+        }
+        Command::Instamint(InstamintCommand::PaybackAddresses) => {
+            let repayment_info = client.load_instamint_payback_addresses().await?;
+            for invalid_address in &repayment_info.invalid_addresses {
+                eprintln!("Skipping {}", invalid_address);
+            }
+            let info: BTreeMap<_, _> = repayment_info.info.into_iter().collect();
+            let value: BTreeMap<&String, Vec<serde_json::Value>> = info.iter()
+                .map(|(symbol, addresses)| {
+                    let addresses = addresses.iter()
+                        .map(|address| json!({
+                            "chain": address.chain.to_string(),
+                            "testnet": address.testnet,
+                            "symbol": address.symbol,
+                            "token": token_identifier(&address.token_identifier),
+                            "address": address.address,
+                        }))
+                        .collect();
+                    (symbol, addresses)
+                })
+                .collect();
+            output.print(json!(value), || {
+                info.iter()
+                    .flat_map(|(symbol, addresses)| addresses.iter().map(move |address| {
+                        format!("{:<16} {} {} ({}): {}", symbol, address.chain, address.symbol, token_identifier(&address.token_identifier), address.address)
+                    }))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+fn fee_lines(config: &AnthicConfigSnapshot) -> Vec<String> {
+    let mut lines = vec!["Settlement fees:".to_string()];
+    lines.extend(config.settlement_fee_per_resource.iter().map(|(symbol, fee)| format!("  {:<16} {}", symbol, fee)));
+    lines.push("Anthic fees per level:".to_string());
+    lines.extend(config.anthic_fee_per_level.iter().enumerate().map(|(level, fee)| {
+        format!("  {:<16} taker {} maker {}", level, fee.taker_fee, fee.maker_fee)
+    }));
+    lines
+}
+
+/// Checks that the hex decodes before handing it to Anthic
+async fn submit_order<T: TradeApiTransport>(client: &AnthicClient<T>, signed_partial_transaction_hex: &str) -> Result<SubmitOrderResponse, Box<dyn Error>> {
+    let signed_partial_transaction = decode_signed_partial_transaction(signed_partial_transaction_hex)?;
+    let signed_partial_transaction_hex = signed_partial_transaction.to_raw()
+        .map_err(|err| format!("Encode error: {:?}", err))?
+        .to_hex();
+    Ok(client.trade_api_client.submit_order(signed_partial_transaction_hex).await?)
+}
+
+/// Builds a taker order, with fees in the sell token for the fee level of the account
+async fn build_order_manifest<T: TradeApiTransport>(client: &AnthicClient<T>, order: &OrderArgs) -> Result<SubintentManifestV2, Box<dyn Error>> {
+    let account = decode_account(client, &order.account)?;
+    let config = client.load_anthic_config().await?;
+    let address_info = client.load_account_address_info(account).await?;
+
+    for symbol in [&order.sell, &order.buy] {
+        if !config.symbol_to_resource.contains_key(symbol) {
+            return Err(format!("Unknown token: {}", symbol).into());
+        }
+    }
+    let settlement_fee_amount = *config.settlement_fee_per_resource.get(&order.sell)
+        .ok_or_else(|| format!("No settlement fee for {}", order.sell))?;
+    let anthic_fee_percent = config.anthic_fee_per_level.get(address_info.level as usize)
+        .ok_or_else(|| format!("No anthic fee for level {}", address_info.level))?
+        .taker_fee;
+    let anthic_fee_amount = order.sell_amount * anthic_fee_percent;
+
+    let sell = OrderLeg::Amount(TokenAmount {
+        symbol: order.sell.clone(),
+        amount: order.sell_amount,
+    });
+    let buy = OrderLeg::Amount(TokenAmount {
+        symbol: order.buy.clone(),
+        amount: order.buy_amount,
+    });
+    let manifest = AnthicSubintentManifestBuilder::new(config)
+        .builder(|builder| builder.assert_worktop_is_empty())
+        .add_anthic_limit_order_with_options(account, sell, buy, settlement_fee_amount, anthic_fee_amount, &order.options())?
+        .build();
+    Ok(manifest)
+}
+
+fn decode_account<T: TradeApiTransport>(client: &AnthicClient<T>, account: &str) -> Result<ComponentAddress, String> {
+    ComponentAddress::try_from_bech32(&client.decoder, account)
+        .ok_or_else(|| format!("Invalid account address for {}: {}", client.network.logical_name, account))
+}
+
+fn decode_signed_partial_transaction(signed_partial_transaction_hex: &str) -> Result<SignedPartialTransactionV2, Box<dyn Error>> {
+    let bytes = hex::decode(signed_partial_transaction_hex.trim())?;
+    SignedPartialTransactionV2::from_raw(&RawSignedPartialTransaction::from_vec(bytes))
+        .map_err(|err| format!("Invalid signed partial transaction: {:?}", err).into())
+}
+
+fn token_identifier(token_identifier: &TokenIdentifierOnChain) -> String {
+    match token_identifier {
+        TokenIdentifierOnChain::Address(address) => address.clone(),
+        TokenIdentifierOnChain::Native => "Native Token".to_string(),
+    }
+}
+
+fn parse_decimal(value: &str) -> Result<Decimal, String> {
+    Decimal::from_str(value).map_err(|err| format!("Invalid decimal {}: {:?}", value, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use anthic_subintents::validate::anthic_validate_manifest;
+    use anthic_trade_api_client::transport::{HttpMethod, InMemoryTransport};
+    use anthic_trade_api_client::AnthicTradeApiClient;
+    use super::*;
+
+    const ACCOUNT: &str = "account_tdx_2_1c8mqe0s0nwjlhtc8qmjl6ylqgvs0yqkq4a5ly4dcpakqtgpw27t3s3";
+    const XUSDC: &str = "resource_tdx_2_1t5pqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszye50ea";
+    const XWBTC: &str = "resource_tdx_2_1t5psxqcrqvpsxqcrqvpsxqcrqvpsxqcrqvpsxqcrqvpsxqcrevzy3u";
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("anthic").chain(args.iter().copied()))
+    }
+
+    fn order_args(extra: &[&str]) -> Result<OrderArgs, clap::Error> {
+        let mut args = vec!["build-order", "--account", ACCOUNT, "--sell", "xUSDC", "--sell-amount", "100", "--buy", "xwBTC", "--buy-amount", "0.001"];
+        args.extend_from_slice(extra);
+        match parse(&args)?.command {
+            Command::BuildOrder(order) => Ok(order),
+            _ => panic!("Expected build-order"),
+        }
+    }
+
+    fn client(transport: InMemoryTransport) -> AnthicClient<InMemoryTransport> {
+        AnthicClient::with_trade_api_client(
+            NetworkDefinition::stokenet(),
+            AnthicTradeApiClient::with_transport(transport, "api-key".to_string()),
+        )
+    }
+
+    fn order_transport() -> InMemoryTransport {
+        InMemoryTransport::new()
+            .respond_json(HttpMethod::Get, "/trade/info", &json!({
+                "verify_parent_access_rule_sbor_hex": "5c220000",
+                "per_token_settlement_fee": [{ "symbol": "xUSDC", "solver_amount": "0.05", "transaction_execution_amount": "0.05" }],
+                "per_level_anthic_fee": [{ "taker_fee": "0.001", "maker_fee": "0" }, { "taker_fee": "0.0005", "maker_fee": "0" }],
+            }))
+            .respond_json(HttpMethod::Get, "/trade/tokens", &json!({
+                "tokens": [
+                    { "symbol": "xUSDC", "resource_address": XUSDC },
+                    { "symbol": "xwBTC", "resource_address": XWBTC },
+                ],
+            }))
+            .respond_json(HttpMethod::Get, &format!("/trade/account_addresses/{}", ACCOUNT), &json!({ "level": 1 }))
+    }
+
+    #[test]
+    fn order_args_are_parsed() {
+        let order = order_args(&[]).unwrap();
+        assert_eq!(order.account, ACCOUNT);
+        assert_eq!((order.sell.as_str(), order.sell_amount), ("xUSDC", dec!(100)));
+        assert_eq!((order.buy.as_str(), order.buy_amount), ("xwBTC", dec!("0.001")));
+        assert_eq!(order.options().buy_constraint, BuyConstraint::AtLeast);
+
+        assert_eq!(order_args(&["--exact"]).unwrap().options().buy_constraint, BuyConstraint::Exact);
+        assert_eq!(
+            order_args(&["--buy-max-amount", "0.002"]).unwrap().options().buy_constraint,
+            BuyConstraint::AmountRange { max: dec!("0.002") },
+        );
+    }
+
+    #[test]
+    fn invalid_order_args_are_rejected() {
+        assert!(order_args(&["--exact", "--buy-max-amount", "0.002"]).is_err());
+        assert!(order_args(&["--buy-max-amount", "a lot"]).is_err());
+        assert!(parse(&["build-order", "--account", ACCOUNT, "--sell", "xUSDC", "--sell-amount", "1.2.3", "--buy", "xwBTC", "--buy-amount", "1"]).is_err());
+    }
+
+    #[test]
+    fn environment_args_are_parsed() {
+        let cli = parse(&["--environment", "mainnet", "tokens"]).unwrap();
+        assert_eq!(cli.environment.network().id, NetworkDefinition::mainnet().id);
+
+        // A network is only meaningful for a custom url
+        assert!(parse(&["--network", "stokenet", "tokens"]).is_err());
+        let cli = parse(&["--url", "http://localhost", "--network", "stokenet", "tokens"]).unwrap();
+        assert_eq!(cli.network.as_deref(), Some("stokenet"));
+    }
+
+    #[tokio::test]
+    async fn authenticated_commands_need_an_api_key() {
+        for args in [vec!["--api-key", "", "instamint", "balance"], vec!["--api-key", "", "submit", "00"]] {
+            let cli = parse(&args).unwrap();
+            assert!(cli.command.requires_api_key());
+
+            let error = run(cli).await.unwrap_err();
+
+            assert!(error.to_string().contains("needs an api key"), "{}", error);
+        }
+        assert!(!parse(&["tokens"]).unwrap().command.requires_api_key());
+    }
+
+    #[tokio::test]
+    async fn build_order_uses_the_taker_fee_of_the_account_level() {
+        let client = client(order_transport());
+        let order = order_args(&["--exact"]).unwrap();
+
+        let manifest = build_order_manifest(&client, &order).await.unwrap();
+
+        let definition = anthic_validate_manifest(&manifest).unwrap();
+        assert_eq!(definition.trade.sell.amount, dec!(100));
+        assert_eq!(definition.trade.buy.amount, dec!("0.001"));
+        assert_eq!(definition.trade.buy_constraint, BuyConstraint::Exact);
+        assert_eq!(definition.fee.settlement_amount, dec!("0.1"));
+        assert_eq!(definition.fee.anthic_amount, dec!("0.05"));
+        assert_eq!(definition.meta.account, decode_account(&client, ACCOUNT).unwrap());
+
+        let decompiled = decompile(&manifest, &client.network).unwrap();
+        assert!(decompiled.contains("YIELD_TO_PARENT"), "{}", decompiled);
+    }
+
+    #[tokio::test]
+    async fn build_order_rejects_unknown_tokens() {
+        let client = client(order_transport());
+        let mut order = order_args(&[]).unwrap();
+        order.buy = "xETH".to_string();
+
+        let error = build_order_manifest(&client, &order).await.unwrap_err();
+
+        assert_eq!(error.to_string(), "Unknown token: xETH");
+    }
+
+    #[tokio::test]
+    async fn submit_posts_the_signed_partial_transaction() {
+        let manifest = build_order_manifest(&client(order_transport()), &order_args(&[]).unwrap()).await.unwrap();
+        let (subintent, _) = SubintentAssembler::new(NetworkDefinition::stokenet(), RandomNonce)
+            .assemble_with_message(manifest, 15, 1, MessageV2::None)
+            .unwrap();
+        let hex = create_signed_partial_transaction(subintent, vec![]).to_raw().unwrap().to_hex();
+        let client = client(InMemoryTransport::new().respond_json(HttpMethod::Post, "/trade/orders", &json!({
+            "subintent_hash": "subtxid_tdx_2_1",
+            "status": "submitted",
+        })));
+
+        let response = submit_order(&client, &hex).await.unwrap();
+
+        assert_eq!(response.status, "submitted");
+        assert_eq!(response.message, None);
+        let requests = client.trade_api_client.transport().requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].body.as_deref().unwrap().contains(&hex));
+        assert!(submit_order(&client, "not hex").await.is_err());
+        assert_eq!(client.trade_api_client.transport().requests().len(), 1);
+    }
+}
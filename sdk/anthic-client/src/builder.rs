@@ -0,0 +1,42 @@
+use radix_common::prelude::*;
+use anthic_trade_api_client::{AnthicTradeApiClientBuilder, TradeApiError};
+use crate::{AnthicClient, AnthicEnvironment};
+
+/// Configures an [`AnthicClient`], see [`AnthicTradeApiClientBuilder`] for the available connection settings
+pub struct AnthicClientBuilder {
+    network: NetworkDefinition,
+    trade_api: AnthicTradeApiClientBuilder,
+}
+
+impl AnthicClientBuilder {
+    pub fn new(network: NetworkDefinition) -> Self {
+        Self {
+            network,
+            trade_api: AnthicTradeApiClientBuilder::new(),
+        }
+    }
+
+    /// Uses the network and api url of a named environment
+    pub fn for_environment(environment: &AnthicEnvironment) -> Self {
+        Self::new(environment.network()).url(environment.trade_api_url())
+    }
+
+    pub fn url(self, url: impl Into<String>) -> Self {
+        self.trade_api(|builder| builder.url(url))
+    }
+
+    pub fn api_key(self, api_key: impl Into<String>) -> Self {
+        self.trade_api(|builder| builder.api_key(api_key))
+    }
+
+    /// Configure the underlying trade api client, e.g. timeouts, proxies or the user agent
+    pub fn trade_api(mut self, update: impl FnOnce(AnthicTradeApiClientBuilder) -> AnthicTradeApiClientBuilder) -> Self {
+        self.trade_api = update(self.trade_api);
+        self
+    }
+
+    pub fn build(self) -> Result<AnthicClient, TradeApiError> {
+        let trade_api_client = self.trade_api.build()?;
+        Ok(AnthicClient::with_trade_api_client(self.network, trade_api_client))
+    }
+}
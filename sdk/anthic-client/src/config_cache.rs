@@ -0,0 +1,148 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+use radix_common::prelude::*;
+use tokio::sync::{broadcast, watch, Mutex};
+use tokio::task::JoinHandle;
+use anthic_model::AnthicConfig;
+use anthic_trade_api_client::transport::TradeApiTransport;
+use crate::{AnthicClient, AnthicClientError};
+
+/// Holds the current [`AnthicConfig`] and refreshes it on demand or on a schedule,
+/// so that long running services always build orders with current parameters.
+pub struct ConfigCache<T> {
+    client: Arc<AnthicClient<T>>,
+    config: watch::Sender<Arc<AnthicConfig>>,
+    diffs: broadcast::Sender<ConfigDiff>,
+    /// Held for a whole refresh, so that a slow refresh cannot replace a newer configuration
+    refresh_lock: Mutex<()>,
+}
+
+impl<T: TradeApiTransport + 'static> ConfigCache<T> {
+    /// Loads the initial configuration
+    pub async fn load(client: Arc<AnthicClient<T>>) -> Result<Self, AnthicClientError> {
+        let config = client.load_anthic_config().await?;
+        let (config, _) = watch::channel(Arc::new(config));
+        let (diffs, _) = broadcast::channel(16);
+        Ok(Self {
+            client,
+            config,
+            diffs,
+            refresh_lock: Mutex::new(()),
+        })
+    }
+
+    pub fn current(&self) -> Arc<AnthicConfig> {
+        self.config.borrow().clone()
+    }
+
+    /// Notified whenever a refresh changes the configuration
+    pub fn subscribe(&self) -> watch::Receiver<Arc<AnthicConfig>> {
+        self.config.subscribe()
+    }
+
+    /// Receives the diff of every refresh which changed the configuration
+    pub fn subscribe_diffs(&self) -> broadcast::Receiver<ConfigDiff> {
+        self.diffs.subscribe()
+    }
+
+    /// Reloads the configuration, returning what changed.
+    ///
+    /// Concurrent refreshes run one after the other, so each diff is against the configuration
+    /// loaded by the previous refresh and the latest load is the one kept.
+    pub async fn refresh(&self) -> Result<ConfigDiff, AnthicClientError> {
+        let _refreshing = self.refresh_lock.lock().await;
+        let new_config = self.client.load_anthic_config().await?;
+        let mut diff = ConfigDiff::default();
+        self.config.send_if_modified(|config| {
+            diff = ConfigDiff::between(config, &new_config);
+            if diff.is_empty() {
+                return false;
+            }
+            *config = Arc::new(new_config);
+            // No diff subscribers is fine
+            let _ = self.diffs.send(diff.clone());
+            true
+        });
+        Ok(diff)
+    }
+
+    /// Refreshes the configuration every `interval` until the returned task is aborted.
+    /// Failed refreshes keep the previous configuration, are passed to `on_error` and are
+    /// retried on the next tick.
+    pub fn spawn_refresh(
+        self: Arc<Self>,
+        interval: Duration,
+        on_error: impl Fn(AnthicClientError) + Send + 'static,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes immediately and the config was just loaded
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(error) = self.refresh().await {
+                    on_error(error);
+                }
+            }
+        })
+    }
+}
+
+/// The difference between two configurations
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConfigDiff {
+    pub added_tokens: Vec<String>,
+    pub removed_tokens: Vec<String>,
+    /// Tokens whose resource address changed
+    pub changed_tokens: Vec<String>,
+    pub changed_settlement_fees: Vec<SettlementFeeChange>,
+    pub anthic_fees_changed: bool,
+    pub verify_parent_access_rule_rotated: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SettlementFeeChange {
+    pub symbol: String,
+    pub old: Option<Decimal>,
+    pub new: Option<Decimal>,
+}
+
+impl ConfigDiff {
+    pub fn between(old: &AnthicConfig, new: &AnthicConfig) -> Self {
+        let mut diff = ConfigDiff::default();
+
+        let symbols: BTreeSet<&String> = old.symbol_to_resource.keys().chain(new.symbol_to_resource.keys()).collect();
+        for symbol in symbols {
+            match (old.symbol_to_resource.get(symbol), new.symbol_to_resource.get(symbol)) {
+                (None, Some(_)) => diff.added_tokens.push(symbol.clone()),
+                (Some(_), None) => diff.removed_tokens.push(symbol.clone()),
+                (Some(old_address), Some(new_address)) if old_address != new_address => diff.changed_tokens.push(symbol.clone()),
+                _ => {}
+            }
+        }
+
+        let symbols: BTreeSet<&String> = old.settlement_fee_per_resource.keys().chain(new.settlement_fee_per_resource.keys()).collect();
+        for symbol in symbols {
+            let old_fee = old.settlement_fee_per_resource.get(symbol).cloned();
+            let new_fee = new.settlement_fee_per_resource.get(symbol).cloned();
+            if old_fee != new_fee {
+                diff.changed_settlement_fees.push(SettlementFeeChange {
+                    symbol: symbol.clone(),
+                    old: old_fee,
+                    new: new_fee,
+                });
+            }
+        }
+
+        diff.anthic_fees_changed = old.anthic_fee_per_level != new.anthic_fee_per_level;
+        diff.verify_parent_access_rule_rotated = old.verify_parent_access_rule != new.verify_parent_access_rule;
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.eq(&ConfigDiff::default())
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt;
+use std::str::FromStr;
+use radix_common::prelude::*;
+
+/// An Anthic deployment, bundling the Radix network with the matching trade api url.
+///
+/// Other deployments, e.g. local test servers, are configured with [`AnthicEnvironment::Custom`].
+#[derive(Clone, Debug)]
+pub enum AnthicEnvironment {
+    /// The production deployment, running against Radix mainnet
+    Mainnet,
+    /// The staging deployment, running against Stokenet
+    Stokenet,
+    Custom {
+        network: NetworkDefinition,
+        trade_api_url: String,
+    },
+}
+
+impl AnthicEnvironment {
+    pub fn network(&self) -> NetworkDefinition {
+        match self {
+            AnthicEnvironment::Mainnet => NetworkDefinition::mainnet(),
+            AnthicEnvironment::Stokenet => NetworkDefinition::stokenet(),
+            AnthicEnvironment::Custom { network, .. } => network.clone(),
+        }
+    }
+
+    pub fn trade_api_url(&self) -> String {
+        match self {
+            AnthicEnvironment::Mainnet => "https://trade-api.anthic.io".to_string(),
+            AnthicEnvironment::Stokenet => "https://trade-api.staging.anthic.io".to_string(),
+            AnthicEnvironment::Custom { trade_api_url, .. } => trade_api_url.clone(),
+        }
+    }
+}
+
+impl FromStr for AnthicEnvironment {
+    type Err = String;
+
+    /// Parses `mainnet`, `stokenet` or `staging`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "mainnet" => Ok(AnthicEnvironment::Mainnet),
+            "stokenet" | "staging" => Ok(AnthicEnvironment::Stokenet),
+            _ => Err(format!("Unknown environment: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for AnthicEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnthicEnvironment::Mainnet => write!(f, "mainnet"),
+            AnthicEnvironment::Stokenet => write!(f, "stokenet"),
+            AnthicEnvironment::Custom { network, trade_api_url } => write!(f, "{} ({})", network.logical_name, trade_api_url),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environments_round_trip_through_their_names() {
+        for name in ["mainnet", "stokenet"] {
+            assert_eq!(AnthicEnvironment::from_str(name).unwrap().to_string(), name);
+        }
+        assert_eq!(AnthicEnvironment::from_str("Staging").unwrap().to_string(), "stokenet");
+        assert!(AnthicEnvironment::from_str("testnet").is_err());
+    }
+
+    #[test]
+    fn mainnet_uses_the_production_api() {
+        let environment = AnthicEnvironment::Mainnet;
+        assert_eq!(environment.network().id, NetworkDefinition::mainnet().id);
+        assert_eq!(environment.trade_api_url(), "https://trade-api.anthic.io");
+    }
+}
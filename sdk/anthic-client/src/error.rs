@@ -0,0 +1,44 @@
+use std::fmt;
+use anthic_trade_api_client::TradeApiError;
+
+/// Errors returned by the high level client
+#[derive(Debug)]
+pub enum AnthicClientError {
+    Api(TradeApiError),
+    /// An address returned by the api does not decode on the configured network,
+    /// e.g. because a mainnet client points at the staging url
+    NetworkMismatch {
+        network: String,
+        symbol: String,
+        address: String,
+    },
+    /// A value returned by the api could not be decoded
+    InvalidResponse(String),
+}
+
+impl fmt::Display for AnthicClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnthicClientError::Api(err) => write!(f, "{}", err),
+            AnthicClientError::NetworkMismatch { network, symbol, address } => {
+                write!(f, "Address {} of {} is not valid on network {}", address, symbol, network)
+            }
+            AnthicClientError::InvalidResponse(message) => write!(f, "Invalid api response: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AnthicClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AnthicClientError::Api(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<TradeApiError> for AnthicClientError {
+    fn from(err: TradeApiError) -> Self {
+        AnthicClientError::Api(err)
+    }
+}
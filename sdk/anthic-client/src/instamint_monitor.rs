@@ -0,0 +1,201 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use radix_common::prelude::*;
+use radix_common_derive::dec;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use anthic_model::{Chain, InstamintHeadroom, InstamintRepaymentInfo, InstamintTokenPaybackAddress};
+use anthic_trade_api_client::transport::TradeApiTransport;
+use crate::{AnthicClient, AnthicClientError};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstamintMonitorConfig {
+    /// Utilisation levels, as a fraction of the allowance, at which events are emitted
+    pub thresholds: Vec<Decimal>,
+    /// Repayments are suggested for tokens whose utilisation is above this fraction
+    pub repay_above: Decimal,
+    /// Suggested repayments bring the utilisation of each token down to this fraction
+    pub repay_to: Decimal,
+    /// Chains to repay on, in order of preference
+    pub payback_chains: Vec<Chain>,
+}
+
+impl Default for InstamintMonitorConfig {
+    fn default() -> Self {
+        Self {
+            thresholds: vec![dec!("0.5"), dec!("0.8"), dec!("0.95")],
+            repay_above: dec!("0.8"),
+            repay_to: dec!("0.5"),
+            payback_chains: vec![Chain::Radix],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstamintLoan {
+    pub symbol: String,
+    pub outstanding: Decimal,
+    /// Outstanding amount as a fraction of the allowance
+    pub utilisation: Decimal,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstamintUtilisation {
+    /// The allowance, which applies to each token separately
+    pub allowance: Decimal,
+    /// Utilisation of the most utilised token
+    pub utilisation: Decimal,
+    /// Outstanding loans, largest first
+    pub loans: Vec<InstamintLoan>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SuggestedRepayment {
+    pub symbol: String,
+    pub amount: Decimal,
+    pub payback_address: InstamintTokenPaybackAddress,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InstamintEvent {
+    /// Utilisation rose to or above the threshold
+    ThresholdExceeded {
+        threshold: Decimal,
+        utilisation: Decimal,
+    },
+    /// Utilisation fell below a previously exceeded threshold
+    ThresholdCleared {
+        threshold: Decimal,
+        utilisation: Decimal,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstamintReport {
+    pub utilisation: InstamintUtilisation,
+    pub suggested_repayments: Vec<SuggestedRepayment>,
+    /// Symbols of outstanding loans with no payback address on any of the payback chains,
+    /// which can therefore not be suggested for repayment
+    pub loans_without_payback_address: Vec<String>,
+    /// One event per threshold crossed since the last poll
+    pub events: Vec<InstamintEvent>,
+}
+
+/// Polls instamint loans and the allowance, emitting events when utilisation crosses thresholds
+pub struct InstamintMonitor<T> {
+    client: Arc<AnthicClient<T>>,
+    config: InstamintMonitorConfig,
+    /// Number of thresholds exceeded on the last poll
+    level: Mutex<usize>,
+    events: broadcast::Sender<InstamintEvent>,
+}
+
+impl<T: TradeApiTransport + 'static> InstamintMonitor<T> {
+    pub fn new(client: Arc<AnthicClient<T>>, mut config: InstamintMonitorConfig) -> Self {
+        config.thresholds.sort();
+        let (events, _) = broadcast::channel(64);
+        Self {
+            client,
+            config,
+            level: Mutex::new(0),
+            events,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<InstamintEvent> {
+        self.events.subscribe()
+    }
+
+    /// Loads loans, allowance and payback addresses and reports on them
+    pub async fn poll(&self) -> Result<InstamintReport, AnthicClientError> {
+        let headroom = self.client.load_instamint_headroom().await?;
+        let repayment_info = self.client.load_instamint_payback_addresses().await?;
+        let report = self.report(&headroom, &repayment_info);
+        for event in &report.events {
+            // No subscribers is fine
+            let _ = self.events.send(event.clone());
+        }
+        Ok(report)
+    }
+
+    /// Polls every `interval` until the returned task is aborted. Failed polls are passed to
+    /// `on_error` and retried on the next tick.
+    pub fn spawn(
+        self: Arc<Self>,
+        interval: Duration,
+        on_error: impl Fn(AnthicClientError) + Send + 'static,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(error) = self.poll().await {
+                    on_error(error);
+                }
+            }
+        })
+    }
+
+    fn report(&self, headroom: &InstamintHeadroom, repayment_info: &InstamintRepaymentInfo) -> InstamintReport {
+        let mut loans: Vec<_> = headroom.outstanding.iter()
+            .filter(|(_, outstanding)| !outstanding.is_zero())
+            .map(|(symbol, outstanding)| InstamintLoan {
+                symbol: symbol.clone(),
+                outstanding: *outstanding,
+                utilisation: headroom.utilisation(symbol),
+            })
+            .collect();
+        loans.sort_by(|a, b| b.utilisation.cmp(&a.utilisation).then_with(|| a.symbol.cmp(&b.symbol)));
+        let utilisation = InstamintUtilisation {
+            allowance: headroom.allowance,
+            utilisation: loans.first().map(|loan| loan.utilisation).unwrap_or_default(),
+            loans,
+        };
+
+        // Thresholds are sorted, so those crossed lie between the previous and the new level
+        let level = self.config.thresholds.iter().filter(|threshold| utilisation.utilisation >= **threshold).count();
+        let previous_level = std::mem::replace(&mut *self.level.lock().unwrap(), level);
+        let events = if level > previous_level {
+            self.config.thresholds[previous_level..level].iter()
+                .map(|threshold| InstamintEvent::ThresholdExceeded {
+                    threshold: *threshold,
+                    utilisation: utilisation.utilisation,
+                })
+                .collect()
+        } else {
+            self.config.thresholds[level..previous_level].iter().rev()
+                .map(|threshold| InstamintEvent::ThresholdCleared {
+                    threshold: *threshold,
+                    utilisation: utilisation.utilisation,
+                })
+                .collect()
+        };
+
+        // Bring each token above the limit back to the target, largest loans first
+        let mut suggested_repayments = Vec::new();
+        let mut loans_without_payback_address = Vec::new();
+        for loan in &utilisation.loans {
+            let payback_address = self.config.payback_chains.iter()
+                .find_map(|chain| repayment_info.get_repayment_address(&loan.symbol, chain));
+            match payback_address {
+                Some(payback_address) if loan.utilisation > self.config.repay_above => {
+                    suggested_repayments.push(SuggestedRepayment {
+                        symbol: loan.symbol.clone(),
+                        amount: loan.outstanding - headroom.allowance * self.config.repay_to,
+                        payback_address,
+                    });
+                }
+                Some(_) => {}
+                None => loans_without_payback_address.push(loan.symbol.clone()),
+            }
+        }
+
+        InstamintReport {
+            utilisation,
+            suggested_repayments,
+            loans_without_payback_address,
+            events,
+        }
+    }
+}
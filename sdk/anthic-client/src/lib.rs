@@ -1,54 +1,103 @@
+mod builder;
+mod config_cache;
+mod environment;
+mod error;
+mod instamint_monitor;
+
 use std::collections::HashMap;
 use radix_common::prelude::*;
 use radix_engine_interface::prelude::*;
 use anthic_model::*;
-use anthic_trade_api_client::AnthicTradeApiClient;
+use anthic_trade_api_client::{AnthicTradeApiClient, TradeApiError};
+use anthic_trade_api_client::transport::{ReqwestTransport, TradeApiTransport};
+
+pub use crate::builder::AnthicClientBuilder;
+pub use crate::config_cache::{ConfigCache, ConfigDiff, SettlementFeeChange};
+pub use crate::environment::AnthicEnvironment;
+pub use crate::error::AnthicClientError;
+pub use crate::instamint_monitor::{
+    InstamintEvent, InstamintLoan, InstamintMonitor, InstamintMonitorConfig, InstamintReport, InstamintUtilisation, SuggestedRepayment,
+};
 
 /// A high level wrapper around the anthic api
-pub struct AnthicClient {
+pub struct AnthicClient<T = ReqwestTransport> {
     pub network: NetworkDefinition,
     pub encoder: AddressBech32Encoder,
     pub decoder: AddressBech32Decoder,
     /// Low level anthic api client
-    pub trade_api_client: AnthicTradeApiClient,
+    pub trade_api_client: AnthicTradeApiClient<T>,
 }
 
 impl AnthicClient {
     pub fn new(network: NetworkDefinition, url: String, api_key: String) -> Self {
+        Self::with_trade_api_client(network, AnthicTradeApiClient::new(url, api_key))
+    }
+
+    /// A client for a named environment, so that the network and api url always match
+    pub fn for_environment(environment: &AnthicEnvironment, api_key: String) -> Self {
+        Self::new(environment.network(), environment.trade_api_url(), api_key)
+    }
+
+    pub fn builder(network: NetworkDefinition) -> AnthicClientBuilder {
+        AnthicClientBuilder::new(network)
+    }
+}
+
+impl<T: TradeApiTransport> AnthicClient<T> {
+    /// Wraps an existing low level client, e.g. one using a custom or in-memory transport
+    pub fn with_trade_api_client(network: NetworkDefinition, trade_api_client: AnthicTradeApiClient<T>) -> Self {
         let decoder = AddressBech32Decoder::new(&network);
         let encoder = AddressBech32Encoder::new(&network);
         Self {
             network,
             decoder,
             encoder,
-            trade_api_client: AnthicTradeApiClient::new(url, api_key),
+            trade_api_client,
+        }
+    }
+
+    /// Checks that the tokens listed by the api decode on the configured network.
+    /// Run this at startup to catch a network paired with the wrong api url.
+    pub async fn check_environment(&self) -> Result<(), AnthicClientError> {
+        let tokens_response = self.trade_api_client.tokens().await?;
+        for token in tokens_response.tokens {
+            if ResourceAddress::try_from_bech32(&self.decoder, &token.resource_address).is_none() {
+                return Err(AnthicClientError::NetworkMismatch {
+                    network: self.network.logical_name.to_string(),
+                    symbol: token.symbol,
+                    address: token.resource_address,
+                });
+            }
         }
+        Ok(())
     }
 
     /// Loads various static configurations from the Anthic API
-    pub async fn load_anthic_config(&self) -> Result<AnthicConfig, reqwest::Error> {
+    pub async fn load_anthic_config(&self) -> Result<AnthicConfig, AnthicClientError> {
         let (verify_parent_access_rule, anthic_fee_per_level, settlement_fee_per_resource)= {
             let anthic_info = self.trade_api_client.info().await?;
-            let verify_parent_access_rule: AccessRule = scrypto_decode(&hex::decode(anthic_info.verify_parent_access_rule_sbor_hex).unwrap()).unwrap();
+            let verify_parent_access_rule: AccessRule = hex::decode(&anthic_info.verify_parent_access_rule_sbor_hex).ok()
+                .and_then(|bytes| scrypto_decode(&bytes).ok())
+                .ok_or_else(|| AnthicClientError::InvalidResponse(format!("Invalid verify parent access rule {}", anthic_info.verify_parent_access_rule_sbor_hex)))?;
             let anthic_taker_fee_per_level = anthic_info.per_level_anthic_fee.into_iter()
                 .map(|level| {
-                    AnthicLevelFee {
-                        taker_fee: Decimal::from_str(&level.taker_fee).unwrap(),
-                        maker_fee: Decimal::from_str(&level.maker_fee).unwrap(),
-                    }
-                }).collect();
+                    Ok(AnthicLevelFee {
+                        taker_fee: decode_decimal(&level.taker_fee)?,
+                        maker_fee: decode_decimal(&level.maker_fee)?,
+                    })
+                }).collect::<Result<_, AnthicClientError>>()?;
             let solver_fee_resources = anthic_info.per_token_settlement_fee.into_iter().map(|info| {
-                (info.symbol, Decimal::from_str(&info.transaction_execution_amount).unwrap() + Decimal::from_str(&info.solver_amount).unwrap())
-            }).collect();
+                Ok((info.symbol, decode_decimal(&info.transaction_execution_amount)? + decode_decimal(&info.solver_amount)?))
+            }).collect::<Result<_, AnthicClientError>>()?;
             (verify_parent_access_rule, anthic_taker_fee_per_level, solver_fee_resources)
         };
 
         let symbol_to_resource: HashMap<String, ResourceAddress> = {
             let tokens_response = self.trade_api_client.tokens().await?;
             tokens_response.tokens.into_iter().map(|t| {
-                let address = ResourceAddress::try_from_bech32(&self.decoder, &t.resource_address).unwrap();
-                (t.symbol, address)
-            }).collect()
+                let address = self.decode_resource_address(&t.symbol, &t.resource_address)?;
+                Ok((t.symbol, address))
+            }).collect::<Result<_, AnthicClientError>>()?
         };
 
         Ok(AnthicConfig {
@@ -59,8 +108,36 @@ impl AnthicClient {
         })
     }
 
+    /// Loads the tokens traded on Anthic with their divisibility and display metadata.
+    /// Tokens without a reported divisibility are loaded with an unknown divisibility.
+    pub async fn load_token_registry(&self) -> Result<TokenRegistry, AnthicClientError> {
+        let tokens_response = self.trade_api_client.tokens().await?;
+        let tokens = tokens_response.tokens.into_iter().map(|t| {
+            if t.divisibility.is_some_and(|divisibility| divisibility > MAX_DIVISIBILITY) {
+                return Err(AnthicClientError::InvalidResponse(format!("Invalid divisibility of {}: {:?}", t.symbol, t.divisibility)));
+            }
+            Ok(TokenInfo {
+                address: self.decode_resource_address(&t.symbol, &t.resource_address)?,
+                symbol: t.symbol,
+                divisibility: t.divisibility,
+                name: t.name,
+                icon_url: t.icon_url,
+            })
+        }).collect::<Result<Vec<_>, AnthicClientError>>()?;
+        Ok(TokenRegistry::new(tokens))
+    }
+
+    /// Loads the token pairs traded on Anthic
+    pub async fn load_token_pairs(&self) -> Result<Vec<TokenPair>, TradeApiError> {
+        let token_pairs_response = self.trade_api_client.token_pairs().await?;
+        Ok(token_pairs_response.token_pairs.into_iter().map(|pair| TokenPair {
+            base: pair.base,
+            quote: pair.quote,
+        }).collect())
+    }
+
     /// Loads instamint-loan-repayment configuration
-    pub async fn load_instamint_config(&self) -> Result<InstamintConfig, reqwest::Error> {
+    pub async fn load_instamint_config(&self) -> Result<InstamintConfig, TradeApiError> {
         let instamint_info = self.trade_api_client.instamint_info().await?;
         let customer_badge_resource = ResourceAddress::try_from_bech32(&self.decoder, &instamint_info.customer_badge_resource).unwrap();
         let instamint_component = ComponentAddress::try_from_bech32(&self.decoder, &instamint_info.instamint_component).unwrap();
@@ -70,65 +147,157 @@ impl AnthicClient {
         })
     }
 
-    pub async fn load_account_address_info(&self, account_address: ComponentAddress) -> Result<AnthicAddressInfo, reqwest::Error> {
+    pub async fn load_account_address_info(&self, account_address: ComponentAddress) -> Result<AnthicAddressInfo, TradeApiError> {
         let address_info = self.trade_api_client.account_address_info(self.encoder.encode(account_address.as_bytes()).unwrap()).await?;
         Ok(AnthicAddressInfo {
             level: address_info.level,
         })
     }
 
-    /// If authenticated, loads the associated Anthic account
-    pub async fn load_anthic_account(&self) -> Result<AnthicAccount, reqwest::Error> {
-        let instamint_account = {
-            let instamint_accounts = self.trade_api_client.instamint_account().await?;
-            instamint_accounts.account
-        };
+    /// If authenticated, loads the on-ledger balances of the accounts managed by the api key.
+    /// Balances of tokens not traded on Anthic are omitted.
+    pub async fn load_on_ledger_accounts(&self) -> Result<Vec<OnLedgerAccount>, AnthicClientError> {
+        let config = self.load_anthic_config().await?;
+        let accounts = self.trade_api_client.accounts().await?;
+
+        let accounts = accounts.accounts.into_iter().map(|account| {
+            let address = self.decode_account_address(&account.address)?;
+            let balances = account.balances.into_iter()
+                .filter_map(|balance| {
+                    let resource = config.symbol_to_resource.get(&balance.symbol)?;
+                    Some(decode_decimal(&balance.amount).map(|amount| (*resource, amount)))
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(OnLedgerAccount {
+                address,
+                balances,
+            })
+        }).collect::<Result<_, AnthicClientError>>()?;
+
+        Ok(accounts)
+    }
+
+    /// If authenticated, loads the Anthic account holding the instamint customer badges
+    pub async fn load_anthic_account(&self) -> Result<AnthicAccount, AnthicClientError> {
+        let accounts = self.load_anthic_accounts().await?;
+        accounts.into_iter().next()
+            .ok_or_else(|| AnthicClientError::InvalidResponse("No Anthic account is managed by the api key".to_string()))
+    }
+
+    /// If authenticated, loads all Anthic accounts managed by the api key.
+    /// The instamint accounts come first, each with its own customer badges, the other accounts have none.
+    pub async fn load_anthic_accounts(&self) -> Result<Vec<AnthicAccount>, AnthicClientError> {
+        let instamint_accounts = self.trade_api_client.instamint_account().await?;
+
+        let mut accounts: Vec<AnthicAccount> = Vec::new();
+        for instamint_account in std::iter::once(instamint_accounts.account).chain(instamint_accounts.accounts) {
+            let address = self.decode_account_address(&instamint_account.address)?;
+            let customer_badge_local_ids = instamint_account.customer_badge_non_fungible_local_ids.iter()
+                .map(|sbor_encoded_local_id| decode_local_id(sbor_encoded_local_id))
+                .collect::<Result<Vec<_>, _>>()?;
+            if accounts.iter().all(|existing| existing.address != address) {
+                accounts.push(AnthicAccount::new(address, customer_badge_local_ids));
+            }
+        }
+        for account in self.trade_api_client.accounts().await?.accounts {
+            let address = self.decode_account_address(&account.address)?;
+            if accounts.iter().all(|existing| existing.address != address) {
+                accounts.push(AnthicAccount::new(address, Vec::new()));
+            }
+        }
+
+        Ok(accounts)
+    }
+
+    /// Loads the Anthic account with the given address, if managed by the api key
+    pub async fn load_anthic_account_by_address(&self, address: ComponentAddress) -> Result<Option<AnthicAccount>, AnthicClientError> {
+        let accounts = self.load_anthic_accounts().await?;
+        Ok(accounts.into_iter().find(|account| account.address == address))
+    }
 
-        let account = ComponentAddress::try_from_bech32(&self.decoder, &instamint_account.address).unwrap();
-        let sbor_encoded_local_id = instamint_account.customer_badge_non_fungible_local_ids.into_iter().next().unwrap();
+    fn decode_account_address(&self, address: &str) -> Result<ComponentAddress, AnthicClientError> {
+        ComponentAddress::try_from_bech32(&self.decoder, address).ok_or_else(|| AnthicClientError::NetworkMismatch {
+            network: self.network.logical_name.to_string(),
+            symbol: "account".to_string(),
+            address: address.to_string(),
+        })
+    }
 
-        let customer_badge_local_id: NonFungibleLocalId = scrypto_decode(&hex::decode(sbor_encoded_local_id).unwrap()).unwrap();
-        Ok(AnthicAccount {
-            address: account,
-            instamint_customer_badge_local_id: Some(customer_badge_local_id),
+    fn decode_resource_address(&self, symbol: &str, address: &str) -> Result<ResourceAddress, AnthicClientError> {
+        ResourceAddress::try_from_bech32(&self.decoder, address).ok_or_else(|| AnthicClientError::NetworkMismatch {
+            network: self.network.logical_name.to_string(),
+            symbol: symbol.to_string(),
+            address: address.to_string(),
         })
     }
 
-    pub async fn load_instamint_payback_addresses(&self) -> Result<InstamintRepaymentInfo, reqwest::Error> {
+    pub async fn load_instamint_payback_addresses(&self) -> Result<InstamintRepaymentInfo, TradeApiError> {
         let payback_addresses = {
             let instamint_payback_addresses = self.trade_api_client.instamint_payback_addresses().await?;
             instamint_payback_addresses.payback_addresses
         };
 
         let tokens = self.trade_api_client.instamint_tokens().await?;
+        let mut invalid_addresses = Vec::new();
         let info: HashMap<String, Vec<InstamintTokenPaybackAddress>> = tokens.tokens.into_iter()
-            .filter(|token| token.chain.eq("Radix"))
+            .filter(|token| Chain::from(token.chain.as_str()) == Chain::Radix)
             .map(|token| {
-                let repayment_addresses: Vec<_> = token.repayment_tokens.iter().flat_map(|repayment_token| {
-                    payback_addresses.iter()
-                        .filter_map(|payback_address| {
-                            if payback_address.chain.eq(&repayment_token.chain) {
-                                Some(InstamintTokenPaybackAddress {
-                                    chain: repayment_token.chain.clone(),
-                                    symbol: repayment_token.symbol.clone(),
-                                    token_identifier: repayment_token.clone().address.map(TokenIdentifierOnChain::Address).unwrap_or(TokenIdentifierOnChain::Native),
-                                    address: payback_address.address.clone(),
-                                })
-                            } else {
-                                None
+                let mut repayment_addresses = Vec::new();
+                for repayment_token in &token.repayment_tokens {
+                    let chain = Chain::from(repayment_token.chain.as_str());
+                    // Radix addresses are checked against the network of the client
+                    let testnet = match chain {
+                        Chain::Radix => self.network.id != NetworkDefinition::mainnet().id,
+                        _ => parse_testnet_flag(repayment_token.testnet.as_deref()),
+                    };
+                    for payback_address in payback_addresses.iter().filter(|payback_address| Chain::from(payback_address.chain.as_str()) == chain) {
+                        // Malformed addresses are reported rather than displayed or sent to
+                        if let Err(err) = chain.validate_address(&payback_address.address, testnet) {
+                            if !invalid_addresses.contains(&err) {
+                                invalid_addresses.push(err);
                             }
-                        })
-                }).collect();
+                            continue;
+                        }
+                        repayment_addresses.push(InstamintTokenPaybackAddress {
+                            chain: chain.clone(),
+                            testnet,
+                            symbol: repayment_token.symbol.clone(),
+                            token_identifier: repayment_token.clone().address.map(TokenIdentifierOnChain::Address).unwrap_or(TokenIdentifierOnChain::Native),
+                            address: payback_address.address.clone(),
+                        });
+                    }
+                }
 
-            (token.symbol, repayment_addresses)
-        }).collect();
+                (token.symbol, repayment_addresses)
+            }).collect();
 
         Ok(InstamintRepaymentInfo {
-            info
+            info,
+            invalid_addresses,
         })
     }
 
-    pub async fn get_instamint_balance(&self) -> Result<HashMap<String, Decimal>, reqwest::Error> {
+    pub async fn get_instamint_allowance(&self) -> Result<Decimal, TradeApiError> {
+        let allowance = self.trade_api_client.instamint_allowance().await?;
+        Ok(Decimal::from_str(&allowance.allowance).unwrap())
+    }
+
+    /// Loads the allowance and outstanding loans and computes the remaining allowance of each
+    /// token, to check mints before building them
+    pub async fn load_instamint_headroom(&self) -> Result<InstamintHeadroom, TradeApiError> {
+        let allowance = self.get_instamint_allowance().await?;
+        let outstanding = self.get_instamint_balance().await?;
+        let remaining_per_token = outstanding.iter()
+            .map(|(symbol, amount)| (symbol.clone(), (allowance - *amount).max(Decimal::zero())))
+            .collect();
+        Ok(InstamintHeadroom {
+            allowance,
+            outstanding,
+            remaining_per_token,
+        })
+    }
+
+    pub async fn get_instamint_balance(&self) -> Result<HashMap<String, Decimal>, TradeApiError> {
         let balances = {
             let instamint_balance = self.trade_api_client.instamint_balance().await?;
             instamint_balance.balances
@@ -141,3 +310,13 @@ impl AnthicClient {
         Ok(outstanding_loans)
     }
 }
+
+fn decode_decimal(value: &str) -> Result<Decimal, AnthicClientError> {
+    Decimal::from_str(value).map_err(|_| AnthicClientError::InvalidResponse(format!("Invalid decimal {}", value)))
+}
+
+fn decode_local_id(sbor_encoded_local_id: &str) -> Result<NonFungibleLocalId, AnthicClientError> {
+    hex::decode(sbor_encoded_local_id).ok()
+        .and_then(|bytes| scrypto_decode(&bytes).ok())
+        .ok_or_else(|| AnthicClientError::InvalidResponse(format!("Invalid customer badge local id {}", sbor_encoded_local_id)))
+}
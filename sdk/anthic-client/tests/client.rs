@@ -0,0 +1,266 @@
+use radix_common::prelude::*;
+use anthic_client::{AnthicClient, AnthicClientError};
+use anthic_model::*;
+use anthic_trade_api_client::model::*;
+use anthic_trade_api_client::transport::{HttpMethod, InMemoryTransport};
+use anthic_trade_api_client::AnthicTradeApiClient;
+
+const INSTAMINT_ACCOUNT: &str = "account_tdx_2_1c8mqe0s0nwjlhtc8qmjl6ylqgvs0yqkq4a5ly4dcpakqtgpw27t3s3";
+const OTHER_ACCOUNT: &str = "account_tdx_2_1cypqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszfpyz4c";
+const USDC_PAYBACK: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+const USDC_ADDRESS: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+fn client(transport: InMemoryTransport) -> AnthicClient<InMemoryTransport> {
+    AnthicClient::with_trade_api_client(
+        NetworkDefinition::stokenet(),
+        AnthicTradeApiClient::with_transport(transport, "api-key".to_string()),
+    )
+}
+
+fn instamint_tokens() -> InstamintTokensResponse {
+    InstamintTokensResponse {
+        tokens: vec![
+            InstamintTokenWithRepaymentInfo {
+                symbol: "xUSDC".to_string(),
+                address: None,
+                chain: "radix".to_string(),
+                repayment_tokens: vec![
+                    InstamintToken {
+                        symbol: "USDC".to_string(),
+                        address: Some(USDC_ADDRESS.to_string()),
+                        chain: "ethereum".to_string(),
+                        testnet: Some("false".to_string()),
+                    },
+                    InstamintToken {
+                        symbol: "BTC".to_string(),
+                        address: None,
+                        chain: "bitcoin".to_string(),
+                        testnet: None,
+                    },
+                ],
+            },
+            // Only tokens issued on Radix can be instaminted
+            InstamintTokenWithRepaymentInfo {
+                symbol: "USDC".to_string(),
+                address: Some(USDC_ADDRESS.to_string()),
+                chain: "ethereum".to_string(),
+                repayment_tokens: vec![],
+            },
+        ],
+    }
+}
+
+fn payback_address(chain: &str, address: &str) -> InstamintPaybackAddress {
+    InstamintPaybackAddress {
+        chain: chain.to_string(),
+        address: address.to_string(),
+    }
+}
+
+fn instamint_account(address: &str, local_ids: &[&str]) -> InstamintAccount {
+    InstamintAccount {
+        customer_badge_non_fungible_local_ids: local_ids.iter().map(|id| id.to_string()).collect(),
+        address: address.to_string(),
+    }
+}
+
+fn instamint_accounts(local_ids: &[&str]) -> InstamintAccountResponse {
+    InstamintAccountResponse {
+        account: instamint_account(INSTAMINT_ACCOUNT, local_ids),
+        accounts: vec![],
+    }
+}
+
+fn accounts_response(addresses: &[&str]) -> AccountsResponse {
+    AccountsResponse {
+        accounts: addresses.iter().map(|address| Account {
+            address: address.to_string(),
+            balances: vec![],
+        }).collect(),
+    }
+}
+
+fn decode_account(address: &str) -> ComponentAddress {
+    ComponentAddress::try_from_bech32(&AddressBech32Decoder::new(&NetworkDefinition::stokenet()), address).unwrap()
+}
+
+#[tokio::test]
+async fn payback_addresses_are_matched_to_repayment_tokens() {
+    let transport = InMemoryTransport::new()
+        .respond_json(HttpMethod::Get, "/instamint/tokens", &instamint_tokens())
+        .respond_json(HttpMethod::Get, "/instamint/account/payback-addresses", &InstamintPaybackAddresses {
+            payback_addresses: vec![
+                payback_address("ethereum", USDC_PAYBACK),
+                payback_address("bitcoin", "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"),
+                // No repayment token on Solana
+                payback_address("solana", "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"),
+            ],
+        });
+
+    let repayment_info = client(transport).load_instamint_payback_addresses().await.unwrap();
+
+    assert_eq!(repayment_info.info.len(), 1);
+    assert_eq!(repayment_info.info["xUSDC"], vec![
+        InstamintTokenPaybackAddress {
+            chain: Chain::Ethereum,
+            testnet: false,
+            symbol: "USDC".to_string(),
+            token_identifier: TokenIdentifierOnChain::Address(USDC_ADDRESS.to_string()),
+            address: USDC_PAYBACK.to_string(),
+        },
+        InstamintTokenPaybackAddress {
+            chain: Chain::Bitcoin,
+            testnet: false,
+            symbol: "BTC".to_string(),
+            token_identifier: TokenIdentifierOnChain::Native,
+            address: "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq".to_string(),
+        },
+    ]);
+    assert!(repayment_info.invalid_addresses.is_empty());
+}
+
+#[tokio::test]
+async fn invalid_payback_addresses_are_reported_once() {
+    let bad_checksum = "0x5aaeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+    let transport = InMemoryTransport::new()
+        .respond_json(HttpMethod::Get, "/instamint/tokens", &instamint_tokens())
+        .respond_json(HttpMethod::Get, "/instamint/account/payback-addresses", &InstamintPaybackAddresses {
+            payback_addresses: vec![
+                payback_address("ethereum", bad_checksum),
+                payback_address("ethereum", bad_checksum),
+                payback_address("bitcoin", "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+            ],
+        });
+
+    let repayment_info = client(transport).load_instamint_payback_addresses().await.unwrap();
+
+    assert!(repayment_info.info["xUSDC"].is_empty());
+    assert_eq!(repayment_info.invalid_addresses.len(), 2);
+    assert!(repayment_info.invalid_addresses[0].contains(bad_checksum));
+    // A testnet address is invalid for a mainnet repayment token
+    assert!(repayment_info.invalid_addresses[1].contains("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"));
+}
+
+#[tokio::test]
+async fn instamint_account_is_loaded_first() {
+    let transport = InMemoryTransport::new()
+        .respond_json(HttpMethod::Get, "/instamint/account", &instamint_accounts(&["5cc0010000000000000001", "5cc0010000000000000002"]))
+        .respond_json(HttpMethod::Get, "/trade/accounts", &accounts_response(&[OTHER_ACCOUNT, INSTAMINT_ACCOUNT]));
+    let client = client(transport);
+
+    let accounts = client.load_anthic_accounts().await.unwrap();
+
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].address, decode_account(INSTAMINT_ACCOUNT));
+    assert_eq!(accounts[0].instamint_customer_badge_local_ids(), [NonFungibleLocalId::integer(1), NonFungibleLocalId::integer(2)]);
+    assert_eq!(accounts[0].instamint_customer_badge_local_id(), Some(&NonFungibleLocalId::integer(1)));
+    assert_eq!(accounts[1].address, decode_account(OTHER_ACCOUNT));
+    assert!(accounts[1].instamint_customer_badge_local_ids().is_empty());
+    assert_eq!(accounts[1].instamint_customer_badge_local_id(), None);
+
+    let account = client.load_anthic_account_by_address(decode_account(OTHER_ACCOUNT)).await.unwrap();
+    assert_eq!(account.as_ref(), accounts.get(1));
+}
+
+#[tokio::test]
+async fn badges_are_loaded_per_instamint_account() {
+    let transport = InMemoryTransport::new()
+        .respond_json(HttpMethod::Get, "/instamint/account", &InstamintAccountResponse {
+            account: instamint_account(INSTAMINT_ACCOUNT, &["5cc0010000000000000001", "5cc0010000000000000002"]),
+            accounts: vec![
+                instamint_account(INSTAMINT_ACCOUNT, &["5cc0010000000000000001", "5cc0010000000000000002"]),
+                instamint_account(OTHER_ACCOUNT, &["5cc0010000000000000003"]),
+            ],
+        })
+        .respond_json(HttpMethod::Get, "/trade/accounts", &accounts_response(&[INSTAMINT_ACCOUNT, OTHER_ACCOUNT]));
+
+    let accounts = client(transport).load_anthic_accounts().await.unwrap();
+
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].instamint_customer_badge_local_ids(), [NonFungibleLocalId::integer(1), NonFungibleLocalId::integer(2)]);
+    assert_eq!(accounts[1].address, decode_account(OTHER_ACCOUNT));
+    assert_eq!(accounts[1].instamint_customer_badge_local_id(), Some(&NonFungibleLocalId::integer(3)));
+
+    let account = accounts[0].clone().select_badge(&NonFungibleLocalId::integer(2)).unwrap();
+    assert_eq!(account.instamint_customer_badge_local_id(), Some(&NonFungibleLocalId::integer(2)));
+    // Badges of another account cannot be selected
+    assert!(accounts[0].clone().select_badge(&NonFungibleLocalId::integer(3)).is_err());
+}
+
+#[tokio::test]
+async fn accounts_on_another_network_are_rejected() {
+    let mainnet_account = "account_rdx1cypqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqsz6wfsxz";
+    let transport = InMemoryTransport::new()
+        .respond_json(HttpMethod::Get, "/instamint/account", &instamint_accounts(&[]))
+        .respond_json(HttpMethod::Get, "/trade/accounts", &accounts_response(&[mainnet_account]));
+
+    let result = client(transport).load_anthic_accounts().await;
+
+    assert!(matches!(result, Err(AnthicClientError::NetworkMismatch { address, .. }) if address == mainnet_account));
+}
+
+#[tokio::test]
+async fn malformed_badge_local_ids_are_rejected() {
+    let transport = InMemoryTransport::new()
+        .respond_json(HttpMethod::Get, "/instamint/account", &instamint_accounts(&["not hex"]))
+        .respond_json(HttpMethod::Get, "/trade/accounts", &accounts_response(&[]));
+
+    let result = client(transport).load_anthic_accounts().await;
+
+    assert!(matches!(result, Err(AnthicClientError::InvalidResponse(_))));
+}
+
+#[tokio::test]
+async fn token_divisibility_is_not_assumed() {
+    let transport = InMemoryTransport::new()
+        .respond_json(HttpMethod::Get, "/trade/tokens", &serde_json::json!({
+            "tokens": [
+                { "symbol": "xUSDC", "resource_address": "resource_tdx_2_1t5pqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszye50ea", "divisibility": 6, "name": "USD Coin" },
+                { "symbol": "xwBTC", "resource_address": "resource_tdx_2_1t5psxqcrqvpsxqcrqvpsxqcrqvpsxqcrqvpsxqcrqvpsxqcrevzy3u" },
+            ],
+        }));
+
+    let tokens = client(transport).load_token_registry().await.unwrap();
+
+    assert_eq!(tokens.divisibility("xUSDC"), Ok(6));
+    assert_eq!(tokens.by_symbol("xUSDC").unwrap().name.as_deref(), Some("USD Coin"));
+    assert_eq!(tokens.divisibility("xwBTC"), Err(TokenRegistryError::UnknownDivisibility("xwBTC".to_string())));
+}
+
+#[tokio::test]
+async fn divisibility_above_the_maximum_is_rejected() {
+    let transport = InMemoryTransport::new()
+        .respond_json(HttpMethod::Get, "/trade/tokens", &serde_json::json!({
+            "tokens": [
+                { "symbol": "xUSDC", "resource_address": "resource_tdx_2_1t5pqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszye50ea", "divisibility": 19 },
+            ],
+        }));
+
+    let result = client(transport).load_token_registry().await;
+
+    assert!(matches!(result, Err(AnthicClientError::InvalidResponse(_))));
+}
+
+#[tokio::test]
+async fn instamint_headroom_is_computed_per_token() {
+    let transport = InMemoryTransport::new()
+        .respond_json(HttpMethod::Get, "/instamint/account/allowance", &serde_json::json!({ "allowance": "1000" }))
+        .respond_json(HttpMethod::Get, "/instamint/account/balance", &serde_json::json!({
+            "balances": [
+                { "symbol": "xUSDC", "amount": "999.5" },
+                { "symbol": "xwBTC", "amount": "1200" },
+            ],
+        }));
+
+    let headroom = client(transport).load_instamint_headroom().await.unwrap();
+
+    assert_eq!(headroom.allowance, dec!(1000));
+    assert_eq!(headroom.remaining("xUSDC"), dec!("0.5"));
+    // Loans above the allowance leave nothing, without reducing other tokens
+    assert_eq!(headroom.remaining("xwBTC"), dec!(0));
+    assert_eq!(headroom.remaining("XRD"), dec!(1000));
+    assert_eq!(headroom.utilisation("xUSDC"), dec!("0.9995"));
+    assert!(headroom.check("xUSDC", dec!("0.5")).is_ok());
+    assert!(headroom.check("xUSDC", dec!("0.500000000000000001")).is_err());
+    assert!(headroom.check("xwBTC", dec!("0.000000000000000001")).is_err());
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use radix_common::prelude::*;
+use radix_engine_interface::prelude::*;
+use serde_json::{json, Value};
+use anthic_client::{AnthicClient, AnthicClientError, ConfigCache, ConfigDiff, SettlementFeeChange};
+use anthic_model::{AnthicConfig, AnthicLevelFee};
+use anthic_trade_api_client::transport::{HttpMethod, InMemoryTransport, TradeApiResponse};
+use anthic_trade_api_client::AnthicTradeApiClient;
+
+const XRD: &str = "resource_tdx_2_1t5qszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgplygsqw";
+const XUSDC: &str = "resource_tdx_2_1t5pqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszye50ea";
+const XWBTC: &str = "resource_tdx_2_1t5psxqcrqvpsxqcrqvpsxqcrqvpsxqcrqvpsxqcrqvpsxqcrevzy3u";
+const OTHER: &str = "resource_tdx_2_1t5zqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyexr0vj";
+
+fn access_rule_hex(rule: AccessRule) -> String {
+    hex::encode(scrypto_encode(&rule).unwrap())
+}
+
+fn info(rule: AccessRule, settlement_fees: &[(&str, &str)], taker_fee: &str) -> Value {
+    json!({
+        "verify_parent_access_rule_sbor_hex": access_rule_hex(rule),
+        "per_token_settlement_fee": settlement_fees.iter().map(|(symbol, amount)| json!({
+            "symbol": symbol,
+            "solver_amount": amount,
+            "transaction_execution_amount": amount,
+        })).collect::<Vec<_>>(),
+        "per_level_anthic_fee": [{ "taker_fee": taker_fee, "maker_fee": "0" }],
+    })
+}
+
+fn tokens(tokens: &[(&str, &str)]) -> Value {
+    json!({
+        "tokens": tokens.iter().map(|(symbol, address)| json!({
+            "symbol": symbol,
+            "resource_address": address,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+fn ok(value: Value) -> TradeApiResponse {
+    TradeApiResponse::new(200, value.to_string())
+}
+
+fn client(transport: InMemoryTransport) -> Arc<AnthicClient<InMemoryTransport>> {
+    Arc::new(AnthicClient::with_trade_api_client(
+        NetworkDefinition::stokenet(),
+        AnthicTradeApiClient::with_transport(transport, String::new()),
+    ))
+}
+
+fn initial(transport: InMemoryTransport) -> InMemoryTransport {
+    transport
+        .respond(HttpMethod::Get, "/trade/info", ok(info(AccessRule::AllowAll, &[("xUSDC", "0.05")], "0.001")))
+        .respond(HttpMethod::Get, "/trade/tokens", ok(tokens(&[("XRD", XRD), ("xUSDC", XUSDC)])))
+}
+
+fn decode(address: &str) -> ResourceAddress {
+    ResourceAddress::try_from_bech32(&AddressBech32Decoder::new(&NetworkDefinition::stokenet()), address).unwrap()
+}
+
+fn config(tokens: &[(&str, &str)], settlement_fees: &[(&str, Decimal)]) -> AnthicConfig {
+    AnthicConfig {
+        verify_parent_access_rule: AccessRule::AllowAll,
+        symbol_to_resource: tokens.iter().map(|(symbol, address)| (symbol.to_string(), decode(address))).collect(),
+        settlement_fee_per_resource: settlement_fees.iter().map(|(symbol, fee)| (symbol.to_string(), *fee)).collect(),
+        anthic_fee_per_level: vec![AnthicLevelFee { taker_fee: dec!("0.001"), maker_fee: dec!(0) }],
+    }
+}
+
+#[tokio::test]
+async fn load_decodes_the_config() {
+    let cache = ConfigCache::load(client(initial(InMemoryTransport::new()))).await.unwrap();
+
+    let config = cache.current();
+    assert_eq!(config.verify_parent_access_rule, AccessRule::AllowAll);
+    assert_eq!(config.symbol_to_resource, HashMap::from([
+        ("XRD".to_string(), decode(XRD)),
+        ("xUSDC".to_string(), decode(XUSDC)),
+    ]));
+    assert_eq!(config.settlement_fee_per_resource, HashMap::from([("xUSDC".to_string(), dec!("0.1"))]));
+    assert_eq!(config.anthic_fee_per_level, vec![AnthicLevelFee { taker_fee: dec!("0.001"), maker_fee: dec!(0) }]);
+}
+
+#[tokio::test]
+async fn refresh_reports_and_publishes_changes() {
+    let transport = initial(InMemoryTransport::new())
+        .respond(HttpMethod::Get, "/trade/info", ok(info(AccessRule::DenyAll, &[("xUSDC", "0.1"), ("xwBTC", "0.0000005")], "0.002")))
+        .respond(HttpMethod::Get, "/trade/tokens", ok(tokens(&[("XRD", XRD), ("xwBTC", XWBTC)])));
+    let cache = ConfigCache::load(client(transport)).await.unwrap();
+    let mut config_changes = cache.subscribe();
+    let mut diffs = cache.subscribe_diffs();
+
+    let diff = cache.refresh().await.unwrap();
+
+    assert_eq!(diff, ConfigDiff {
+        added_tokens: vec!["xwBTC".to_string()],
+        removed_tokens: vec!["xUSDC".to_string()],
+        changed_tokens: vec![],
+        changed_settlement_fees: vec![
+            SettlementFeeChange { symbol: "xUSDC".to_string(), old: Some(dec!("0.1")), new: Some(dec!("0.2")) },
+            SettlementFeeChange { symbol: "xwBTC".to_string(), old: None, new: Some(dec!("0.000001")) },
+        ],
+        anthic_fees_changed: true,
+        verify_parent_access_rule_rotated: true,
+    });
+    assert!(config_changes.has_changed().unwrap());
+    assert_eq!(diffs.try_recv().unwrap(), diff);
+    assert_eq!(cache.current().verify_parent_access_rule, AccessRule::DenyAll);
+
+    // Nothing changed since
+    let diff = cache.refresh().await.unwrap();
+    assert!(diff.is_empty());
+    assert!(diffs.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn concurrent_refreshes_report_each_change_once() {
+    let transport = initial(InMemoryTransport::new())
+        .respond(HttpMethod::Get, "/trade/tokens", ok(tokens(&[("XRD", XRD), ("xUSDC", XUSDC), ("xwBTC", XWBTC)])));
+    let cache = ConfigCache::load(client(transport)).await.unwrap();
+    let mut diffs = cache.subscribe_diffs();
+
+    let (first, second) = tokio::join!(cache.refresh(), cache.refresh());
+
+    let mut results = vec![first.unwrap(), second.unwrap()];
+    results.sort_by_key(|diff| diff.is_empty());
+    assert_eq!(results[0].added_tokens, vec!["xwBTC".to_string()]);
+    assert!(results[1].is_empty());
+    assert_eq!(diffs.try_recv().unwrap(), results[0]);
+    assert!(diffs.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn invalid_responses_are_returned_as_errors() {
+    let invalid_responses = [
+        ("/trade/info", json!({
+            "verify_parent_access_rule_sbor_hex": "not hex",
+            "per_token_settlement_fee": [],
+            "per_level_anthic_fee": [],
+        })),
+        ("/trade/info", info(AccessRule::AllowAll, &[("xUSDC", "a lot")], "0.001")),
+        ("/trade/info", info(AccessRule::AllowAll, &[], "0.1%")),
+    ];
+    for (path, response) in invalid_responses {
+        let transport = initial(InMemoryTransport::new()).respond(HttpMethod::Get, path, ok(response));
+        let cache = ConfigCache::load(client(transport)).await.unwrap();
+
+        let result = cache.refresh().await;
+
+        assert!(matches!(result, Err(AnthicClientError::InvalidResponse(_))), "{:?}", result);
+        assert_eq!(cache.current().settlement_fee_per_resource["xUSDC"], dec!("0.1"));
+    }
+}
+
+#[tokio::test]
+async fn tokens_from_another_network_are_rejected() {
+    let mainnet_address = "resource_rdx1t5qszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgphsgu3m";
+    let transport = initial(InMemoryTransport::new())
+        .respond(HttpMethod::Get, "/trade/tokens", ok(tokens(&[("XRD", mainnet_address)])));
+    let cache = ConfigCache::load(client(transport)).await.unwrap();
+
+    let result = cache.refresh().await;
+
+    assert!(matches!(result, Err(AnthicClientError::NetworkMismatch { symbol, .. }) if symbol == "XRD"));
+}
+
+#[tokio::test]
+async fn scheduled_refresh_errors_are_passed_to_on_error() {
+    let transport = initial(InMemoryTransport::new())
+        .respond(HttpMethod::Get, "/trade/info", TradeApiResponse::new(500, "down"));
+    let cache = Arc::new(ConfigCache::load(client(transport)).await.unwrap());
+    let (errors, mut received) = tokio::sync::mpsc::unbounded_channel();
+
+    let task = cache.clone().spawn_refresh(Duration::from_millis(5), move |error| {
+        let _ = errors.send(error);
+    });
+    let error = tokio::time::timeout(Duration::from_secs(5), received.recv()).await.unwrap().unwrap();
+    task.abort();
+
+    assert!(matches!(error, AnthicClientError::Api(_)));
+    assert_eq!(cache.current().symbol_to_resource.len(), 2);
+}
+
+#[test]
+fn diff_reports_changed_addresses() {
+    let old = config(&[("XRD", XRD), ("xUSDC", XUSDC)], &[("xUSDC", dec!("0.1"))]);
+    let new = config(&[("XRD", XRD), ("xUSDC", OTHER)], &[("xUSDC", dec!("0.1"))]);
+
+    let diff = ConfigDiff::between(&old, &new);
+
+    assert_eq!(diff.changed_tokens, vec!["xUSDC".to_string()]);
+    assert!(diff.added_tokens.is_empty() && diff.removed_tokens.is_empty());
+    assert!(diff.changed_settlement_fees.is_empty());
+    assert!(!diff.anthic_fees_changed && !diff.verify_parent_access_rule_rotated);
+}
+
+#[test]
+fn diff_of_equal_configs_is_empty() {
+    let config = config(&[("XRD", XRD), ("xUSDC", XUSDC)], &[("xUSDC", dec!("0.1"))]);
+    assert!(ConfigDiff::between(&config, &config.clone()).is_empty());
+    assert!(ConfigDiff::default().is_empty());
+}
+
+#[test]
+fn diff_reports_removed_settlement_fees() {
+    let old = config(&[("XRD", XRD)], &[("xUSDC", dec!("0.1")), ("XRD", dec!(1))]);
+    let new = config(&[("XRD", XRD)], &[("XRD", dec!(1))]);
+
+    let diff = ConfigDiff::between(&old, &new);
+
+    assert_eq!(diff.changed_settlement_fees, vec![SettlementFeeChange {
+        symbol: "xUSDC".to_string(),
+        old: Some(dec!("0.1")),
+        new: None,
+    }]);
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+use std::time::Duration;
+use radix_common::prelude::*;
+use serde_json::{json, Value};
+use anthic_client::{AnthicClient, AnthicClientError, InstamintEvent, InstamintMonitor, InstamintMonitorConfig};
+use anthic_trade_api_client::transport::{HttpMethod, InMemoryTransport, TradeApiResponse};
+use anthic_trade_api_client::AnthicTradeApiClient;
+
+const PAYBACK_ACCOUNT: &str = "account_tdx_2_1cypqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszfpyz4c";
+const XUSDC: &str = "resource_tdx_2_1t5pqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszye50ea";
+
+fn balances(balances: &[(&str, &str)]) -> Value {
+    json!({
+        "balances": balances.iter().map(|(symbol, amount)| json!({ "symbol": symbol, "amount": amount })).collect::<Vec<_>>(),
+    })
+}
+
+/// An allowance of 100, with xUSDC repayable on Radix and xwBTC only on Bitcoin
+fn transport() -> InMemoryTransport {
+    InMemoryTransport::new()
+        .respond_json(HttpMethod::Get, "/instamint/account/allowance", &json!({ "allowance": "100" }))
+        .respond_json(HttpMethod::Get, "/instamint/tokens", &json!({
+            "tokens": [
+                {
+                    "symbol": "xUSDC",
+                    "chain": "radix",
+                    "repayment_tokens": [{ "symbol": "xUSDC", "address": XUSDC, "chain": "radix" }],
+                },
+                {
+                    "symbol": "xwBTC",
+                    "chain": "radix",
+                    "repayment_tokens": [{ "symbol": "BTC", "chain": "bitcoin" }],
+                },
+            ],
+        }))
+        .respond_json(HttpMethod::Get, "/instamint/account/payback-addresses", &json!({
+            "payback_addresses": [{ "chain": "radix", "address": PAYBACK_ACCOUNT }],
+        }))
+}
+
+fn monitor(transport: InMemoryTransport) -> InstamintMonitor<InMemoryTransport> {
+    let client = AnthicClient::with_trade_api_client(
+        NetworkDefinition::stokenet(),
+        AnthicTradeApiClient::with_transport(transport, "api-key".to_string()),
+    );
+    InstamintMonitor::new(Arc::new(client), InstamintMonitorConfig::default())
+}
+
+#[tokio::test]
+async fn utilisation_is_that_of_the_most_utilised_token() {
+    let monitor = monitor(transport()
+        .respond_json(HttpMethod::Get, "/instamint/account/balance", &balances(&[("xUSDC", "90"), ("xwBTC", "30"), ("XRD", "0")])));
+
+    let report = monitor.poll().await.unwrap();
+
+    assert_eq!(report.utilisation.allowance, dec!(100));
+    assert_eq!(report.utilisation.utilisation, dec!("0.9"));
+    let loans: Vec<_> = report.utilisation.loans.iter().map(|loan| (loan.symbol.as_str(), loan.utilisation)).collect();
+    assert_eq!(loans, vec![("xUSDC", dec!("0.9")), ("xwBTC", dec!("0.3"))]);
+}
+
+#[tokio::test]
+async fn repayments_are_suggested_for_loans_above_the_limit() {
+    let monitor = monitor(transport()
+        .respond_json(HttpMethod::Get, "/instamint/account/balance", &balances(&[("xUSDC", "90"), ("xwBTC", "85")])));
+
+    let report = monitor.poll().await.unwrap();
+
+    // Back down to half the allowance
+    assert_eq!(report.suggested_repayments.len(), 1);
+    assert_eq!(report.suggested_repayments[0].symbol, "xUSDC");
+    assert_eq!(report.suggested_repayments[0].amount, dec!(40));
+    assert_eq!(report.suggested_repayments[0].payback_address.address, PAYBACK_ACCOUNT);
+    // xwBTC is above the limit too, but can only be repaid on Bitcoin
+    assert_eq!(report.loans_without_payback_address, vec!["xwBTC".to_string()]);
+}
+
+#[tokio::test]
+async fn an_event_is_emitted_for_each_threshold_crossed() {
+    let monitor = monitor(transport()
+        .respond_json(HttpMethod::Get, "/instamint/account/balance", &balances(&[("xUSDC", "60")]))
+        .respond_json(HttpMethod::Get, "/instamint/account/balance", &balances(&[("xUSDC", "96")]))
+        .respond_json(HttpMethod::Get, "/instamint/account/balance", &balances(&[("xUSDC", "96")]))
+        .respond_json(HttpMethod::Get, "/instamint/account/balance", &balances(&[("xUSDC", "10")])));
+    let mut events = monitor.subscribe();
+
+    let exceeded = |threshold: &str, utilisation: &str| InstamintEvent::ThresholdExceeded {
+        threshold: Decimal::from_str(threshold).unwrap(),
+        utilisation: Decimal::from_str(utilisation).unwrap(),
+    };
+    let cleared = |threshold: &str| InstamintEvent::ThresholdCleared {
+        threshold: Decimal::from_str(threshold).unwrap(),
+        utilisation: dec!("0.1"),
+    };
+    assert_eq!(monitor.poll().await.unwrap().events, vec![exceeded("0.5", "0.6")]);
+    assert_eq!(monitor.poll().await.unwrap().events, vec![exceeded("0.8", "0.96"), exceeded("0.95", "0.96")]);
+    assert!(monitor.poll().await.unwrap().events.is_empty());
+    assert_eq!(monitor.poll().await.unwrap().events, vec![cleared("0.95"), cleared("0.8"), cleared("0.5")]);
+
+    let mut received = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        received.push(event);
+    }
+    assert_eq!(received.len(), 6);
+}
+
+#[tokio::test]
+async fn failed_polls_are_passed_to_on_error() {
+    let monitor = Arc::new(monitor(transport()
+        .respond(HttpMethod::Get, "/instamint/account/balance", TradeApiResponse::new(500, "down"))));
+    let (errors, mut received) = tokio::sync::mpsc::unbounded_channel();
+
+    let task = monitor.spawn(Duration::from_millis(5), move |error| {
+        let _ = errors.send(error);
+    });
+    let error = tokio::time::timeout(Duration::from_secs(5), received.recv()).await.unwrap().unwrap();
+    task.abort();
+
+    assert!(matches!(error, AnthicClientError::Api(_)));
+}
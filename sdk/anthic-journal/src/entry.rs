@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use anthic_subintents::lifecycle::SubintentState;
+use anthic_subintents::render::RenderedOrder;
+use crate::error::JournalError;
+
+/// A journaled order. Fields are flat strings so that entries export directly to CSV.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Bech32 encoded subintent hash
+    pub subintent_hash: String,
+    pub created_at_secs: i64,
+    pub updated_at_secs: i64,
+    /// One of the [`SubintentState`] names, e.g. `signed` or `filled`
+    pub status: String,
+    /// e.g. the reason an order was rejected
+    pub status_message: Option<String>,
+    pub account: String,
+    pub sell_resource: String,
+    pub sell_symbol: Option<String>,
+    pub sell_amount: String,
+    pub buy_resource: String,
+    pub buy_symbol: Option<String>,
+    /// The least amount to receive, the most being the `buy_max_amount` of `order_json`
+    pub buy_amount: String,
+    pub fee_resource: String,
+    pub fee_symbol: Option<String>,
+    pub anthic_fee_amount: String,
+    pub settlement_fee_amount: String,
+    pub signed_partial_transaction_hex: Option<String>,
+    pub client_order_id: Option<String>,
+    /// The whole [`RenderedOrder`] as JSON, including details without a column of their own
+    /// such as non-fungible ids and the buy range
+    pub order_json: String,
+}
+
+impl JournalEntry {
+    /// A newly signed order
+    pub fn new(subintent_hash: String, order: &RenderedOrder, created_at_secs: i64) -> Result<Self, JournalError> {
+        Ok(Self {
+            subintent_hash,
+            created_at_secs,
+            updated_at_secs: created_at_secs,
+            status: status_name(&SubintentState::Signed).to_string(),
+            status_message: None,
+            account: order.account.clone(),
+            sell_resource: order.sell.resource.clone(),
+            sell_symbol: order.sell.symbol.clone(),
+            sell_amount: order.sell.amount.clone(),
+            buy_resource: order.buy.resource.clone(),
+            buy_symbol: order.buy.symbol.clone(),
+            buy_amount: order.buy.amount.clone(),
+            fee_resource: order.fee.resource.clone(),
+            fee_symbol: order.fee.symbol.clone(),
+            anthic_fee_amount: order.fee.anthic_amount.clone(),
+            settlement_fee_amount: order.fee.settlement_amount.clone(),
+            signed_partial_transaction_hex: None,
+            client_order_id: order.client_order_id.clone(),
+            order_json: serde_json::to_string(order)?,
+        })
+    }
+
+    pub fn with_signed_partial_transaction_hex(mut self, hex: String) -> Self {
+        self.signed_partial_transaction_hex = Some(hex);
+        self
+    }
+
+    /// e.g. `xUSDC/xwBTC`, using resource addresses for unknown tokens
+    pub fn pair(&self) -> String {
+        format!(
+            "{}/{}",
+            self.sell_symbol.as_ref().unwrap_or(&self.sell_resource),
+            self.buy_symbol.as_ref().unwrap_or(&self.buy_resource),
+        )
+    }
+}
+
+pub fn status_name(state: &SubintentState) -> &'static str {
+    match state {
+        SubintentState::Signed => "signed",
+        SubintentState::Submitted => "submitted",
+        SubintentState::Accepted => "accepted",
+        SubintentState::Filled => "filled",
+        SubintentState::Expired => "expired",
+        SubintentState::Rejected { .. } => "rejected",
+    }
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum JournalError {
+    Sqlite(rusqlite::Error),
+    Json(serde_json::Error),
+    Csv(csv::Error),
+    /// No entry exists for the subintent hash
+    NotFound(String),
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalError::Sqlite(err) => write!(f, "SQLite error: {}", err),
+            JournalError::Json(err) => write!(f, "JSON error: {}", err),
+            JournalError::Csv(err) => write!(f, "CSV error: {}", err),
+            JournalError::NotFound(hash) => write!(f, "No journal entry for {}", hash),
+        }
+    }
+}
+
+impl std::error::Error for JournalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JournalError::Sqlite(err) => Some(err),
+            JournalError::Json(err) => Some(err),
+            JournalError::Csv(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for JournalError {
+    fn from(err: rusqlite::Error) -> Self {
+        JournalError::Sqlite(err)
+    }
+}
+
+impl From<serde_json::Error> for JournalError {
+    fn from(err: serde_json::Error) -> Self {
+        JournalError::Json(err)
+    }
+}
+
+impl From<csv::Error> for JournalError {
+    fn from(err: csv::Error) -> Self {
+        JournalError::Csv(err)
+    }
+}
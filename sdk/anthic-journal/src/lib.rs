@@ -0,0 +1,388 @@
+mod entry;
+mod error;
+
+use std::io::Write;
+use std::path::Path;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row, ToSql};
+use anthic_subintents::lifecycle::SubintentState;
+
+pub use crate::entry::{status_name, JournalEntry};
+pub use crate::error::JournalError;
+
+const COLUMNS: &str = "subintent_hash, created_at_secs, updated_at_secs, status, status_message, account, \
+    sell_resource, sell_symbol, sell_amount, buy_resource, buy_symbol, buy_amount, \
+    fee_resource, fee_symbol, anthic_fee_amount, settlement_fee_amount, signed_partial_transaction_hex, client_order_id, order_json";
+
+/// Filters for [`Journal::query`], unset fields match all entries
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JournalQuery {
+    /// Inclusive lower bound on the creation time
+    pub from_secs: Option<i64>,
+    /// Exclusive upper bound on the creation time
+    pub to_secs: Option<i64>,
+    /// Token symbols of a pair, matching orders in either direction
+    pub pair: Option<(String, String)>,
+    pub status: Option<String>,
+}
+
+/// An audit trail of built, signed and submitted orders, stored in SQLite
+pub struct Journal {
+    connection: Connection,
+}
+
+impl Journal {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, JournalError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// A journal which is lost when dropped, for tests
+    pub fn in_memory() -> Result<Self, JournalError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, JournalError> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS orders (
+                subintent_hash TEXT PRIMARY KEY,
+                created_at_secs INTEGER NOT NULL,
+                updated_at_secs INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                status_message TEXT,
+                account TEXT NOT NULL,
+                sell_resource TEXT NOT NULL,
+                sell_symbol TEXT,
+                sell_amount TEXT NOT NULL,
+                buy_resource TEXT NOT NULL,
+                buy_symbol TEXT,
+                buy_amount TEXT NOT NULL,
+                fee_resource TEXT NOT NULL,
+                fee_symbol TEXT,
+                anthic_fee_amount TEXT NOT NULL,
+                settlement_fee_amount TEXT NOT NULL,
+                signed_partial_transaction_hex TEXT,
+                client_order_id TEXT,
+                order_json TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS orders_client_order_id ON orders (client_order_id);
+            CREATE INDEX IF NOT EXISTS orders_created_at ON orders (created_at_secs);
+            CREATE INDEX IF NOT EXISTS orders_status ON orders (status);",
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Records a new order, failing if its subintent hash is already journaled
+    pub fn record(&self, entry: &JournalEntry) -> Result<(), JournalError> {
+        self.connection.execute(
+            &format!("INSERT INTO orders ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)", COLUMNS),
+            params![
+                entry.subintent_hash,
+                entry.created_at_secs,
+                entry.updated_at_secs,
+                entry.status,
+                entry.status_message,
+                entry.account,
+                entry.sell_resource,
+                entry.sell_symbol,
+                entry.sell_amount,
+                entry.buy_resource,
+                entry.buy_symbol,
+                entry.buy_amount,
+                entry.fee_resource,
+                entry.fee_symbol,
+                entry.anthic_fee_amount,
+                entry.settlement_fee_amount,
+                entry.signed_partial_transaction_hex,
+                entry.client_order_id,
+                entry.order_json,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_signed_partial_transaction_hex(&self, subintent_hash: &str, hex: &str, now_secs: i64) -> Result<(), JournalError> {
+        let updated = self.connection.execute(
+            "UPDATE orders SET signed_partial_transaction_hex = ?2, updated_at_secs = ?3 WHERE subintent_hash = ?1",
+            params![subintent_hash, hex, now_secs],
+        )?;
+        Self::expect_updated(updated, subintent_hash)
+    }
+
+    pub fn update_status(&self, subintent_hash: &str, status: &str, status_message: Option<&str>, now_secs: i64) -> Result<(), JournalError> {
+        let updated = self.connection.execute(
+            "UPDATE orders SET status = ?2, status_message = ?3, updated_at_secs = ?4 WHERE subintent_hash = ?1",
+            params![subintent_hash, status, status_message, now_secs],
+        )?;
+        Self::expect_updated(updated, subintent_hash)
+    }
+
+    /// Records a state of the subintent lifecycle tracker
+    pub fn update_state(&self, subintent_hash: &str, state: &SubintentState, now_secs: i64) -> Result<(), JournalError> {
+        let status_message = match state {
+            SubintentState::Rejected { reason } => Some(reason.as_str()),
+            _ => None,
+        };
+        self.update_status(subintent_hash, status_name(state), status_message, now_secs)
+    }
+
+    pub fn get_by_client_order_id(&self, client_order_id: &str) -> Result<Option<JournalEntry>, JournalError> {
+        let entry = self.connection
+            .query_row(
+                &format!("SELECT {} FROM orders WHERE client_order_id = ?1", COLUMNS),
+                params![client_order_id],
+                Self::read_entry,
+            )
+            .optional()?;
+        Ok(entry)
+    }
+
+    pub fn get(&self, subintent_hash: &str) -> Result<Option<JournalEntry>, JournalError> {
+        let entry = self.connection
+            .query_row(
+                &format!("SELECT {} FROM orders WHERE subintent_hash = ?1", COLUMNS),
+                params![subintent_hash],
+                Self::read_entry,
+            )
+            .optional()?;
+        Ok(entry)
+    }
+
+    /// Entries matching the query, oldest first
+    pub fn query(&self, query: &JournalQuery) -> Result<Vec<JournalEntry>, JournalError> {
+        let mut conditions: Vec<String> = Vec::new();
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+        if let Some(from_secs) = query.from_secs {
+            values.push(Box::new(from_secs));
+            conditions.push(format!("created_at_secs >= ?{}", values.len()));
+        }
+        if let Some(to_secs) = query.to_secs {
+            values.push(Box::new(to_secs));
+            conditions.push(format!("created_at_secs < ?{}", values.len()));
+        }
+        if let Some((a, b)) = &query.pair {
+            values.push(Box::new(a.clone()));
+            let a = values.len();
+            values.push(Box::new(b.clone()));
+            let b = values.len();
+            conditions.push(format!(
+                "((sell_symbol = ?{a} AND buy_symbol = ?{b}) OR (sell_symbol = ?{b} AND buy_symbol = ?{a}))"
+            ));
+        }
+        if let Some(status) = &query.status {
+            values.push(Box::new(status.clone()));
+            conditions.push(format!("status = ?{}", values.len()));
+        }
+
+        let mut sql = format!("SELECT {} FROM orders", COLUMNS);
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY created_at_secs, subintent_hash");
+
+        let mut statement = self.connection.prepare(&sql)?;
+        let entries = statement
+            .query_map(params_from_iter(values.iter()), Self::read_entry)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    fn read_entry(row: &Row) -> rusqlite::Result<JournalEntry> {
+        Ok(JournalEntry {
+            subintent_hash: row.get(0)?,
+            created_at_secs: row.get(1)?,
+            updated_at_secs: row.get(2)?,
+            status: row.get(3)?,
+            status_message: row.get(4)?,
+            account: row.get(5)?,
+            sell_resource: row.get(6)?,
+            sell_symbol: row.get(7)?,
+            sell_amount: row.get(8)?,
+            buy_resource: row.get(9)?,
+            buy_symbol: row.get(10)?,
+            buy_amount: row.get(11)?,
+            fee_resource: row.get(12)?,
+            fee_symbol: row.get(13)?,
+            anthic_fee_amount: row.get(14)?,
+            settlement_fee_amount: row.get(15)?,
+            signed_partial_transaction_hex: row.get(16)?,
+            client_order_id: row.get(17)?,
+            order_json: row.get(18)?,
+        })
+    }
+
+    fn expect_updated(updated: usize, subintent_hash: &str) -> Result<(), JournalError> {
+        if updated == 0 {
+            return Err(JournalError::NotFound(subintent_hash.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Writes entries as CSV with a header row, e.g. for accounting
+pub fn export_csv(entries: &[JournalEntry], writer: impl Write) -> Result<(), JournalError> {
+    let mut writer = csv::Writer::from_writer(writer);
+    for entry in entries {
+        writer.serialize(entry)?;
+    }
+    writer.flush().map_err(csv::Error::from)?;
+    Ok(())
+}
+
+/// Writes entries as a pretty printed JSON array
+pub fn export_json(entries: &[JournalEntry], writer: impl Write) -> Result<(), JournalError> {
+    serde_json::to_writer_pretty(writer, entries)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anthic_subintents::render::{RenderedAmount, RenderedFee, RenderedOrder};
+    use anthic_subintents::validate::FeeSource;
+
+    fn amount(symbol: &str, amount: &str) -> RenderedAmount {
+        RenderedAmount {
+            resource: format!("resource_{}", symbol),
+            symbol: Some(symbol.to_string()),
+            amount: amount.to_string(),
+            non_fungible_ids: None,
+        }
+    }
+
+    fn order(sell: RenderedAmount, buy: RenderedAmount) -> RenderedOrder {
+        RenderedOrder {
+            account: "account_1".to_string(),
+            fee: RenderedFee {
+                resource: sell.resource.clone(),
+                symbol: sell.symbol.clone(),
+                anthic_amount: "2".to_string(),
+                settlement_amount: "1".to_string(),
+                total_amount: "3".to_string(),
+                source: FeeSource::Sell,
+            },
+            sell,
+            buy,
+            buy_max_amount: None,
+            verify_parent_access_rule_sbor_hex: "00".to_string(),
+            client_order_id: None,
+        }
+    }
+
+    fn entry(hash: &str, sell: RenderedAmount, buy: RenderedAmount, created_at_secs: i64) -> JournalEntry {
+        JournalEntry::new(hash.to_string(), &order(sell, buy), created_at_secs).unwrap()
+    }
+
+    /// A buy and a sell of xwBTC for xUSDC, and an XRD order, an hour apart
+    fn journal() -> Journal {
+        let journal = Journal::in_memory().unwrap();
+        journal.record(&entry("hash_1", amount("xUSDC", "100"), amount("xwBTC", "0.001"), 0)).unwrap();
+        journal.record(&entry("hash_2", amount("xwBTC", "0.001"), amount("xUSDC", "100"), 3600)).unwrap();
+        journal.record(&entry("hash_3", amount("XRD", "1000"), amount("xUSDC", "10"), 7200)).unwrap();
+        journal
+    }
+
+    fn hashes(entries: Vec<JournalEntry>) -> Vec<String> {
+        entries.into_iter().map(|entry| entry.subintent_hash).collect()
+    }
+
+    #[test]
+    fn entries_round_trip_with_the_rendered_order() {
+        let journal = journal();
+        let mut order = order(amount("xUSDC", "100"), amount("xwBTC", "0.001"));
+        order.buy_max_amount = Some("0.002".to_string());
+        order.client_order_id = Some("order-42".to_string());
+        let entry = JournalEntry::new("hash_4".to_string(), &order, 10).unwrap()
+            .with_signed_partial_transaction_hex("abcd".to_string());
+
+        journal.record(&entry).unwrap();
+
+        assert_eq!(journal.get("hash_4").unwrap(), Some(entry.clone()));
+        assert_eq!(journal.get_by_client_order_id("order-42").unwrap(), Some(entry.clone()));
+        assert_eq!(serde_json::from_str::<RenderedOrder>(&entry.order_json).unwrap(), order);
+        assert_eq!(entry.pair(), "xUSDC/xwBTC");
+        assert_eq!(journal.get("hash_5").unwrap(), None);
+    }
+
+    #[test]
+    fn duplicate_subintent_hashes_are_rejected() {
+        let journal = journal();
+
+        let result = journal.record(&entry("hash_1", amount("XRD", "1"), amount("xUSDC", "1"), 10));
+
+        assert!(matches!(result, Err(JournalError::Sqlite(_))));
+        assert_eq!(journal.get("hash_1").unwrap().unwrap().sell_amount, "100");
+    }
+
+    #[test]
+    fn states_are_recorded_with_the_rejected_reason() {
+        let journal = journal();
+
+        journal.update_state("hash_1", &SubintentState::Filled, 100).unwrap();
+        journal.update_state("hash_2", &SubintentState::Rejected { reason: "insufficient funds".to_string() }, 200).unwrap();
+        journal.set_signed_partial_transaction_hex("hash_3", "abcd", 300).unwrap();
+
+        let filled = journal.get("hash_1").unwrap().unwrap();
+        assert_eq!((filled.status.as_str(), filled.status_message, filled.updated_at_secs), ("filled", None, 100));
+        let rejected = journal.get("hash_2").unwrap().unwrap();
+        assert_eq!((rejected.status.as_str(), rejected.status_message.as_deref()), ("rejected", Some("insufficient funds")));
+        assert_eq!(journal.get("hash_3").unwrap().unwrap().signed_partial_transaction_hex.as_deref(), Some("abcd"));
+        assert!(matches!(
+            journal.update_state("hash_5", &SubintentState::Filled, 100),
+            Err(JournalError::NotFound(hash)) if hash == "hash_5"
+        ));
+    }
+
+    #[test]
+    fn queries_filter_by_time_range() {
+        let journal = journal();
+
+        let query = |from_secs, to_secs| hashes(journal.query(&JournalQuery {
+            from_secs,
+            to_secs,
+            ..Default::default()
+        }).unwrap());
+
+        assert_eq!(query(None, None), vec!["hash_1", "hash_2", "hash_3"]);
+        assert_eq!(query(Some(3600), None), vec!["hash_2", "hash_3"]);
+        assert_eq!(query(None, Some(3600)), vec!["hash_1"]);
+        assert_eq!(query(Some(1), Some(7200)), vec!["hash_2"]);
+    }
+
+    #[test]
+    fn queries_match_pairs_in_either_direction_and_status() {
+        let journal = journal();
+        journal.update_state("hash_2", &SubintentState::Filled, 4000).unwrap();
+
+        let pair = Some(("xwBTC".to_string(), "xUSDC".to_string()));
+        assert_eq!(hashes(journal.query(&JournalQuery {
+            pair: pair.clone(),
+            ..Default::default()
+        }).unwrap()), vec!["hash_1", "hash_2"]);
+        assert_eq!(hashes(journal.query(&JournalQuery {
+            pair,
+            status: Some("filled".to_string()),
+            ..Default::default()
+        }).unwrap()), vec!["hash_2"]);
+        assert_eq!(hashes(journal.query(&JournalQuery {
+            status: Some("signed".to_string()),
+            ..Default::default()
+        }).unwrap()), vec!["hash_1", "hash_3"]);
+    }
+
+    #[test]
+    fn entries_export_to_csv_and_json() {
+        let entries = journal().query(&JournalQuery::default()).unwrap();
+
+        let mut csv = Vec::new();
+        export_csv(&entries, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("subintent_hash,created_at_secs,updated_at_secs,status,"));
+        assert!(lines[1].starts_with("hash_1,0,0,signed,,account_1,resource_xUSDC,xUSDC,100,"));
+
+        let mut json = Vec::new();
+        export_json(&entries, &mut json).unwrap();
+        assert_eq!(serde_json::from_slice::<Vec<JournalEntry>>(&json).unwrap(), entries);
+    }
+}
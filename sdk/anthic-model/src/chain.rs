@@ -0,0 +1,247 @@
+use std::fmt;
+use bech32::{FromBase32, Variant};
+use sha3::{Digest, Keccak256};
+
+/// A chain on which instamint tokens are issued or repaid
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Chain {
+    Radix,
+    Ethereum,
+    Arbitrum,
+    Base,
+    Bsc,
+    Polygon,
+    Avalanche,
+    Bitcoin,
+    Solana,
+    Tron,
+    /// A chain this version of the SDK does not know about
+    Unknown(String),
+}
+
+impl Chain {
+    pub fn name(&self) -> &str {
+        match self {
+            Chain::Radix => "Radix",
+            Chain::Ethereum => "Ethereum",
+            Chain::Arbitrum => "Arbitrum",
+            Chain::Base => "Base",
+            Chain::Bsc => "BSC",
+            Chain::Polygon => "Polygon",
+            Chain::Avalanche => "Avalanche",
+            Chain::Bitcoin => "Bitcoin",
+            Chain::Solana => "Solana",
+            Chain::Tron => "Tron",
+            Chain::Unknown(name) => name,
+        }
+    }
+
+    /// Whether addresses on this chain are EVM addresses
+    pub fn is_evm(&self) -> bool {
+        matches!(
+            self,
+            Chain::Ethereum | Chain::Arbitrum | Chain::Base | Chain::Bsc | Chain::Polygon | Chain::Avalanche
+        )
+    }
+
+    /// Checks that an address is well formed for this chain, including its checksum.
+    /// Addresses on unknown chains are only checked to be non-empty without whitespace.
+    pub fn validate_address(&self, address: &str, testnet: bool) -> Result<(), String> {
+        let result = match self {
+            Chain::Radix => validate_radix_address(address, testnet),
+            Chain::Bitcoin => validate_bitcoin_address(address, testnet),
+            Chain::Solana => validate_solana_address(address),
+            Chain::Tron => validate_tron_address(address),
+            chain if chain.is_evm() => validate_evm_address(address),
+            _ => {
+                if address.is_empty() || address.contains(char::is_whitespace) {
+                    Err("malformed address".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+        };
+        result.map_err(|err| format!("Invalid {} address {}: {}", self, address, err))
+    }
+}
+
+impl From<&str> for Chain {
+    fn from(name: &str) -> Self {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "radix" | "xrd" => Chain::Radix,
+            "ethereum" | "eth" => Chain::Ethereum,
+            "arbitrum" | "arb" => Chain::Arbitrum,
+            "base" => Chain::Base,
+            "bsc" | "bnb" | "binance smart chain" => Chain::Bsc,
+            "polygon" | "matic" => Chain::Polygon,
+            "avalanche" | "avax" => Chain::Avalanche,
+            "bitcoin" | "btc" => Chain::Bitcoin,
+            "solana" | "sol" => Chain::Solana,
+            "tron" | "trx" => Chain::Tron,
+            _ => Chain::Unknown(name.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The api flags testnet tokens with an optional string
+pub fn parse_testnet_flag(testnet: Option<&str>) -> bool {
+    match testnet.map(|testnet| testnet.trim().to_ascii_lowercase()) {
+        None => false,
+        Some(testnet) => !(testnet.is_empty() || testnet == "false" || testnet == "0"),
+    }
+}
+
+fn validate_radix_address(address: &str, testnet: bool) -> Result<(), String> {
+    let (hrp, data, variant) = bech32::decode(address).map_err(|err| err.to_string())?;
+    if variant != Variant::Bech32m {
+        return Err("not bech32m".to_string());
+    }
+    let expected_network = if testnet { "_tdx_" } else { "_rdx" };
+    if !hrp.contains(expected_network) {
+        return Err(format!("not a {} address", if testnet { "testnet" } else { "mainnet" }));
+    }
+    let bytes = Vec::<u8>::from_base32(&data).map_err(|err| err.to_string())?;
+    // Radix node ids are 30 bytes
+    if bytes.len() != 30 {
+        return Err("wrong length".to_string());
+    }
+    Ok(())
+}
+
+fn validate_evm_address(address: &str) -> Result<(), String> {
+    let hex = address.strip_prefix("0x").ok_or("missing 0x prefix")?;
+    if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("not 20 hex encoded bytes".to_string());
+    }
+    // Single case addresses carry no checksum
+    if hex == hex.to_ascii_lowercase() || hex == hex.to_ascii_uppercase() {
+        return Ok(());
+    }
+
+    // EIP-55: a letter is uppercase iff the corresponding nibble of the hash of the lowercase address is >= 8
+    let hash = Keccak256::digest(hex.to_ascii_lowercase().as_bytes());
+    for (i, c) in hex.chars().enumerate() {
+        let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0xf;
+        if c.is_ascii_alphabetic() && c.is_ascii_uppercase() != (nibble >= 8) {
+            return Err("bad EIP-55 checksum".to_string());
+        }
+    }
+    Ok(())
+}
+
+fn validate_bitcoin_address(address: &str, testnet: bool) -> Result<(), String> {
+    let segwit_hrp = if testnet { "tb" } else { "bc" };
+    if address.to_ascii_lowercase().starts_with(&format!("{}1", segwit_hrp)) {
+        let (hrp, data, variant) = bech32::decode(address).map_err(|err| err.to_string())?;
+        if hrp != segwit_hrp {
+            return Err("wrong network".to_string());
+        }
+        let (version, program) = data.split_first().ok_or("missing witness version")?;
+        let version = version.to_u8();
+        let program = Vec::<u8>::from_base32(program).map_err(|err| err.to_string())?;
+        let valid = match version {
+            0 => variant == Variant::Bech32 && (program.len() == 20 || program.len() == 32),
+            1..=16 => variant == Variant::Bech32m && (2..=40).contains(&program.len()),
+            _ => false,
+        };
+        return if valid { Ok(()) } else { Err("invalid witness program".to_string()) };
+    }
+
+    let bytes = bs58::decode(address).with_check(None).into_vec().map_err(|err| err.to_string())?;
+    let (version, payload) = bytes.split_first().ok_or("empty")?;
+    // P2PKH and P2SH version bytes
+    let versions: [u8; 2] = if testnet { [0x6f, 0xc4] } else { [0x00, 0x05] };
+    if !versions.contains(version) || payload.len() != 20 {
+        return Err("wrong version or length".to_string());
+    }
+    Ok(())
+}
+
+fn validate_solana_address(address: &str) -> Result<(), String> {
+    let bytes = bs58::decode(address).into_vec().map_err(|err| err.to_string())?;
+    if bytes.len() != 32 {
+        return Err("not a 32 byte public key".to_string());
+    }
+    Ok(())
+}
+
+fn validate_tron_address(address: &str) -> Result<(), String> {
+    let bytes = bs58::decode(address).with_check(None).into_vec().map_err(|err| err.to_string())?;
+    if bytes.len() != 21 || bytes[0] != 0x41 {
+        return Err("wrong version or length".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evm_addresses_are_checked_against_eip55() {
+        assert!(Chain::Ethereum.validate_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", false).is_ok());
+        assert!(Chain::Bsc.validate_address("0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359", false).is_ok());
+        assert!(Chain::Ethereum.validate_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", false).is_ok());
+        assert!(Chain::Ethereum.validate_address("0x5aaeb6053F3E94C9b9A09f33669435E7Ef1BeAed", false).is_err());
+        assert!(Chain::Ethereum.validate_address("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", false).is_err());
+        assert!(Chain::Ethereum.validate_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA", false).is_err());
+    }
+
+    #[test]
+    fn bitcoin_segwit_addresses() {
+        assert!(Chain::Bitcoin.validate_address("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq", false).is_ok());
+        assert!(Chain::Bitcoin.validate_address("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", false).is_ok());
+        assert!(Chain::Bitcoin.validate_address("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0", false).is_ok());
+        assert!(Chain::Bitcoin.validate_address("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", true).is_ok());
+        assert!(Chain::Bitcoin.validate_address("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", false).is_err());
+        assert!(Chain::Bitcoin.validate_address("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5", false).is_err());
+    }
+
+    #[test]
+    fn bitcoin_base58check_addresses() {
+        assert!(Chain::Bitcoin.validate_address("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2", false).is_ok());
+        assert!(Chain::Bitcoin.validate_address("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy", false).is_ok());
+        assert!(Chain::Bitcoin.validate_address("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN3", false).is_err());
+        assert!(Chain::Bitcoin.validate_address("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2", true).is_err());
+    }
+
+    #[test]
+    fn tron_addresses() {
+        assert!(Chain::Tron.validate_address("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", false).is_ok());
+        assert!(Chain::Tron.validate_address("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6u", false).is_err());
+        assert!(Chain::Tron.validate_address("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2", false).is_err());
+    }
+
+    #[test]
+    fn solana_addresses() {
+        assert!(Chain::Solana.validate_address("11111111111111111111111111111111", false).is_ok());
+        assert!(Chain::Solana.validate_address("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", false).is_ok());
+        assert!(Chain::Solana.validate_address("1111111111111111111111111111111", false).is_err());
+        assert!(Chain::Solana.validate_address("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt10", false).is_err());
+    }
+
+    #[test]
+    fn radix_addresses_are_checked_against_the_network() {
+        let mainnet = "resource_rdx1tknxxxxxxxxxradxrdxxxxxxxxx009923554798xxxxxxxxxradxrd";
+        let stokenet = "resource_tdx_2_1tknxxxxxxxxxradxrdxxxxxxxxx009923554798xxxxxxxxxtfd2jc";
+        assert!(Chain::Radix.validate_address(mainnet, false).is_ok());
+        assert!(Chain::Radix.validate_address(stokenet, true).is_ok());
+        assert!(Chain::Radix.validate_address(mainnet, true).is_err());
+        assert!(Chain::Radix.validate_address(stokenet, false).is_err());
+        assert!(Chain::Radix.validate_address("resource_rdx1tknxxxxxxxxxradxrdxxxxxxxxx009923554798xxxxxxxxxradxrc", false).is_err());
+    }
+
+    #[test]
+    fn unknown_chains_only_reject_blank_addresses() {
+        let chain = Chain::from("cardano");
+        assert!(chain.validate_address("addr1anything", false).is_ok());
+        assert!(chain.validate_address("", false).is_err());
+        assert!(chain.validate_address("addr1 anything", false).is_err());
+    }
+}
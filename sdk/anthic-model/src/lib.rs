@@ -1,6 +1,14 @@
+mod chain;
+#[cfg(feature = "serde")]
+pub mod snapshot;
+mod tokens;
+
 use radix_common::prelude::*;
 use radix_engine_interface::prelude::*;
 
+pub use crate::chain::*;
+pub use crate::tokens::*;
+
 /// Anthic configuration
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct AnthicConfig {
@@ -32,7 +40,39 @@ pub struct InstamintConfig {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct AnthicAccount {
     pub address: ComponentAddress,
-    pub instamint_customer_badge_local_id: Option<NonFungibleLocalId>,
+    /// All customer badges held by the account
+    instamint_customer_badge_local_ids: Vec<NonFungibleLocalId>,
+    /// Index of the customer badge used to instamint
+    selected_badge: Option<usize>,
+}
+
+impl AnthicAccount {
+    /// An account holding the given customer badges, instaminting with the first of them
+    pub fn new(address: ComponentAddress, instamint_customer_badge_local_ids: Vec<NonFungibleLocalId>) -> Self {
+        let selected_badge = (!instamint_customer_badge_local_ids.is_empty()).then_some(0);
+        Self {
+            address,
+            instamint_customer_badge_local_ids,
+            selected_badge,
+        }
+    }
+
+    pub fn instamint_customer_badge_local_ids(&self) -> &[NonFungibleLocalId] {
+        &self.instamint_customer_badge_local_ids
+    }
+
+    /// The customer badge used to instamint, if the account holds any
+    pub fn instamint_customer_badge_local_id(&self) -> Option<&NonFungibleLocalId> {
+        self.selected_badge.map(|index| &self.instamint_customer_badge_local_ids[index])
+    }
+
+    /// Selects which customer badge to instamint with
+    pub fn select_badge(mut self, local_id: &NonFungibleLocalId) -> Result<Self, String> {
+        let index = self.instamint_customer_badge_local_ids.iter().position(|id| id == local_id)
+            .ok_or_else(|| format!("Account does not hold customer badge {}", local_id))?;
+        self.selected_badge = Some(index);
+        Ok(self)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -54,7 +94,8 @@ pub enum TokenIdentifierOnChain {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct InstamintTokenPaybackAddress {
-    pub chain: String,
+    pub chain: Chain,
+    pub testnet: bool,
     pub symbol: String,
     pub token_identifier: TokenIdentifierOnChain,
     pub address: String,
@@ -63,11 +104,57 @@ pub struct InstamintTokenPaybackAddress {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct InstamintRepaymentInfo {
     pub info: HashMap<String, Vec<InstamintTokenPaybackAddress>>,
+    /// Payback addresses which failed validation for their chain, and were left out of `info`
+    pub invalid_addresses: Vec<String>,
 }
 
 impl InstamintRepaymentInfo {
-    pub fn get_repayment_address(&self, symbol: &str, chain: &str) -> Option<InstamintTokenPaybackAddress> {
+    pub fn get_repayment_address(&self, symbol: &str, chain: &Chain) -> Option<InstamintTokenPaybackAddress> {
         let payback_addresses = self.info.get(symbol)?;
         payback_addresses.iter().find(|a| a.chain.eq(chain)).cloned()
     }
-}
\ No newline at end of file
+}
+
+/// Remaining instamint capacity of an account, per token.
+///
+/// The allowance applies to each token separately, so a loan of one token does not reduce what
+/// may be minted of another.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstamintHeadroom {
+    pub allowance: Decimal,
+    /// Outstanding loans per token symbol
+    pub outstanding: HashMap<String, Decimal>,
+    /// Remaining allowance per token symbol with an outstanding loan
+    pub remaining_per_token: HashMap<String, Decimal>,
+}
+
+impl InstamintHeadroom {
+    pub fn outstanding(&self, symbol: &str) -> Decimal {
+        self.outstanding.get(symbol).cloned().unwrap_or_default()
+    }
+
+    /// How much more of the token may be instaminted. Tokens without a loan have the whole allowance.
+    pub fn remaining(&self, symbol: &str) -> Decimal {
+        self.remaining_per_token.get(symbol).cloned().unwrap_or(self.allowance)
+    }
+
+    /// Outstanding loan of the token as a fraction of the allowance
+    pub fn utilisation(&self, symbol: &str) -> Decimal {
+        let outstanding = self.outstanding(symbol);
+        if self.allowance.is_positive() {
+            outstanding / self.allowance
+        } else if outstanding.is_positive() {
+            Decimal::ONE
+        } else {
+            Decimal::zero()
+        }
+    }
+
+    pub fn check(&self, symbol: &str, amount: Decimal) -> Result<(), String> {
+        let remaining = self.remaining(symbol);
+        if amount > remaining {
+            return Err(format!("Instamint of {} {} exceeds remaining allowance of {} {}", amount, symbol, remaining, symbol));
+        }
+        Ok(())
+    }
+}
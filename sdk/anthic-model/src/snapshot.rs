@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use radix_common::prelude::*;
+use radix_engine_interface::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::{AnthicAccount, AnthicConfig, AnthicLevelFee, InstamintConfig, OnLedgerAccount};
+
+/// Converts a model type to and from a serializable form, with addresses as bech32 for a given network
+/// and access rules as SBOR hex, so that it may be stored for offline use, audits or tests.
+pub trait Snapshot: Sized {
+    type Repr: Serialize + DeserializeOwned;
+
+    fn to_snapshot(&self, network: &NetworkDefinition) -> Result<Self::Repr, SnapshotError>;
+
+    fn from_snapshot(repr: Self::Repr, network: &NetworkDefinition) -> Result<Self, SnapshotError>;
+
+    /// Writes the snapshot as JSON, tagged with the network it was taken on
+    fn save(&self, path: impl AsRef<Path>, network: &NetworkDefinition) -> Result<(), SnapshotError> {
+        let file = SnapshotFile {
+            network: network.logical_name.to_string(),
+            snapshot: self.to_snapshot(network)?,
+        };
+        fs::write(path, serde_json::to_string_pretty(&file)?)?;
+        Ok(())
+    }
+
+    /// Reads a snapshot written by [`Snapshot::save`], failing if it was taken on another network
+    fn load(path: impl AsRef<Path>, network: &NetworkDefinition) -> Result<Self, SnapshotError> {
+        let file: SnapshotFile<Self::Repr> = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if file.network != network.logical_name {
+            return Err(SnapshotError::NetworkMismatch {
+                expected: network.logical_name.to_string(),
+                found: file.network,
+            });
+        }
+        Self::from_snapshot(file.snapshot, network)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotFile<R> {
+    network: String,
+    snapshot: R,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Encode(String),
+    Decode(String),
+    NetworkMismatch { expected: String, found: String },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "IO error: {}", err),
+            SnapshotError::Json(err) => write!(f, "JSON error: {}", err),
+            SnapshotError::Encode(message) => write!(f, "Encode error: {}", message),
+            SnapshotError::Decode(message) => write!(f, "Decode error: {}", message),
+            SnapshotError::NetworkMismatch { expected, found } => {
+                write!(f, "Snapshot was taken on {} but {} was expected", found, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(err: std::io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(err: serde_json::Error) -> Self {
+        SnapshotError::Json(err)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnthicConfigSnapshot {
+    pub verify_parent_access_rule_sbor_hex: String,
+    pub symbol_to_resource: BTreeMap<String, String>,
+    pub settlement_fee_per_resource: BTreeMap<String, String>,
+    pub anthic_fee_per_level: Vec<AnthicLevelFeeSnapshot>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnthicLevelFeeSnapshot {
+    pub taker_fee: String,
+    pub maker_fee: String,
+}
+
+impl Snapshot for AnthicConfig {
+    type Repr = AnthicConfigSnapshot;
+
+    fn to_snapshot(&self, network: &NetworkDefinition) -> Result<Self::Repr, SnapshotError> {
+        let encoder = AddressBech32Encoder::new(network);
+        let verify_parent_access_rule = scrypto_encode(&self.verify_parent_access_rule)
+            .map_err(|err| SnapshotError::Encode(format!("{:?}", err)))?;
+        let symbol_to_resource = self.symbol_to_resource.iter()
+            .map(|(symbol, resource)| Ok((symbol.clone(), encode_address(&encoder, resource.as_bytes())?)))
+            .collect::<Result<_, SnapshotError>>()?;
+
+        Ok(AnthicConfigSnapshot {
+            verify_parent_access_rule_sbor_hex: hex::encode(verify_parent_access_rule),
+            symbol_to_resource,
+            settlement_fee_per_resource: self.settlement_fee_per_resource.iter()
+                .map(|(symbol, fee)| (symbol.clone(), fee.to_string()))
+                .collect(),
+            anthic_fee_per_level: self.anthic_fee_per_level.iter()
+                .map(|level| AnthicLevelFeeSnapshot {
+                    taker_fee: level.taker_fee.to_string(),
+                    maker_fee: level.maker_fee.to_string(),
+                })
+                .collect(),
+        })
+    }
+
+    fn from_snapshot(repr: Self::Repr, network: &NetworkDefinition) -> Result<Self, SnapshotError> {
+        let decoder = AddressBech32Decoder::new(network);
+        let access_rule_bytes = hex::decode(&repr.verify_parent_access_rule_sbor_hex)
+            .map_err(|err| SnapshotError::Decode(format!("verify_parent_access_rule: {}", err)))?;
+        let verify_parent_access_rule: AccessRule = scrypto_decode(&access_rule_bytes)
+            .map_err(|err| SnapshotError::Decode(format!("verify_parent_access_rule: {:?}", err)))?;
+
+        let symbol_to_resource = repr.symbol_to_resource.into_iter()
+            .map(|(symbol, address)| {
+                let resource = ResourceAddress::try_from_bech32(&decoder, &address)
+                    .ok_or_else(|| SnapshotError::Decode(format!("Invalid resource address for {}: {}", symbol, address)))?;
+                Ok((symbol, resource))
+            })
+            .collect::<Result<_, SnapshotError>>()?;
+        let settlement_fee_per_resource = repr.settlement_fee_per_resource.into_iter()
+            .map(|(symbol, fee)| Ok((symbol, decode_decimal(&fee)?)))
+            .collect::<Result<_, SnapshotError>>()?;
+        let anthic_fee_per_level = repr.anthic_fee_per_level.into_iter()
+            .map(|level| {
+                Ok(AnthicLevelFee {
+                    taker_fee: decode_decimal(&level.taker_fee)?,
+                    maker_fee: decode_decimal(&level.maker_fee)?,
+                })
+            })
+            .collect::<Result<_, SnapshotError>>()?;
+
+        Ok(AnthicConfig {
+            verify_parent_access_rule,
+            symbol_to_resource,
+            settlement_fee_per_resource,
+            anthic_fee_per_level,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstamintConfigSnapshot {
+    pub customer_badge_resource: String,
+    pub instamint_component: String,
+}
+
+impl Snapshot for InstamintConfig {
+    type Repr = InstamintConfigSnapshot;
+
+    fn to_snapshot(&self, network: &NetworkDefinition) -> Result<Self::Repr, SnapshotError> {
+        let encoder = AddressBech32Encoder::new(network);
+        Ok(InstamintConfigSnapshot {
+            customer_badge_resource: encode_address(&encoder, self.customer_badge_resource.as_bytes())?,
+            instamint_component: encode_address(&encoder, self.instamint_component.as_bytes())?,
+        })
+    }
+
+    fn from_snapshot(repr: Self::Repr, network: &NetworkDefinition) -> Result<Self, SnapshotError> {
+        let decoder = AddressBech32Decoder::new(network);
+        Ok(InstamintConfig {
+            customer_badge_resource: ResourceAddress::try_from_bech32(&decoder, &repr.customer_badge_resource)
+                .ok_or_else(|| SnapshotError::Decode(format!("Invalid resource address: {}", repr.customer_badge_resource)))?,
+            instamint_component: ComponentAddress::try_from_bech32(&decoder, &repr.instamint_component)
+                .ok_or_else(|| SnapshotError::Decode(format!("Invalid component address: {}", repr.instamint_component)))?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnthicAccountSnapshot {
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instamint_customer_badge_local_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub instamint_customer_badge_local_ids: Vec<String>,
+}
+
+impl Snapshot for AnthicAccount {
+    type Repr = AnthicAccountSnapshot;
+
+    fn to_snapshot(&self, network: &NetworkDefinition) -> Result<Self::Repr, SnapshotError> {
+        let encoder = AddressBech32Encoder::new(network);
+        Ok(AnthicAccountSnapshot {
+            address: encode_address(&encoder, self.address.as_bytes())?,
+            instamint_customer_badge_local_id: self.instamint_customer_badge_local_id().map(|id| id.to_string()),
+            instamint_customer_badge_local_ids: self.instamint_customer_badge_local_ids().iter().map(|id| id.to_string()).collect(),
+        })
+    }
+
+    fn from_snapshot(repr: Self::Repr, network: &NetworkDefinition) -> Result<Self, SnapshotError> {
+        let decoder = AddressBech32Decoder::new(network);
+        let decode_local_id = |id: String| NonFungibleLocalId::from_str(&id)
+            .map_err(|err| SnapshotError::Decode(format!("Invalid local id {}: {:?}", id, err)));
+        let instamint_customer_badge_local_id = repr.instamint_customer_badge_local_id
+            .map(decode_local_id)
+            .transpose()?;
+        let instamint_customer_badge_local_ids = repr.instamint_customer_badge_local_ids.into_iter()
+            .map(decode_local_id)
+            .collect::<Result<_, _>>()?;
+        let address = ComponentAddress::try_from_bech32(&decoder, &repr.address)
+            .ok_or_else(|| SnapshotError::Decode(format!("Invalid account address: {}", repr.address)))?;
+        let account = AnthicAccount::new(address, instamint_customer_badge_local_ids);
+        match instamint_customer_badge_local_id {
+            Some(local_id) => account.select_badge(&local_id).map_err(SnapshotError::Decode),
+            None => Ok(account),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OnLedgerAccountSnapshot {
+    pub address: String,
+    pub balances: BTreeMap<String, String>,
+}
+
+impl Snapshot for OnLedgerAccount {
+    type Repr = OnLedgerAccountSnapshot;
+
+    fn to_snapshot(&self, network: &NetworkDefinition) -> Result<Self::Repr, SnapshotError> {
+        let encoder = AddressBech32Encoder::new(network);
+        let balances = self.balances.iter()
+            .map(|(resource, amount)| Ok((encode_address(&encoder, resource.as_bytes())?, amount.to_string())))
+            .collect::<Result<_, SnapshotError>>()?;
+        Ok(OnLedgerAccountSnapshot {
+            address: encode_address(&encoder, self.address.as_bytes())?,
+            balances,
+        })
+    }
+
+    fn from_snapshot(repr: Self::Repr, network: &NetworkDefinition) -> Result<Self, SnapshotError> {
+        let decoder = AddressBech32Decoder::new(network);
+        let balances = repr.balances.into_iter()
+            .map(|(resource, amount)| {
+                let address = ResourceAddress::try_from_bech32(&decoder, &resource)
+                    .ok_or_else(|| SnapshotError::Decode(format!("Invalid resource address: {}", resource)))?;
+                Ok((address, decode_decimal(&amount)?))
+            })
+            .collect::<Result<_, SnapshotError>>()?;
+        Ok(OnLedgerAccount {
+            address: ComponentAddress::try_from_bech32(&decoder, &repr.address)
+                .ok_or_else(|| SnapshotError::Decode(format!("Invalid account address: {}", repr.address)))?,
+            balances,
+        })
+    }
+}
+
+fn encode_address(encoder: &AddressBech32Encoder, address: &[u8]) -> Result<String, SnapshotError> {
+    encoder.encode(address).map_err(|err| SnapshotError::Encode(format!("{:?}", err)))
+}
+
+fn decode_decimal(value: &str) -> Result<Decimal, SnapshotError> {
+    Decimal::from_str(value).map_err(|err| SnapshotError::Decode(format!("Invalid decimal {}: {:?}", value, err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("anthic-snapshot-{}-{}.json", name, std::process::id()))
+    }
+
+    fn resource(entity_type: EntityType, byte: u8) -> ResourceAddress {
+        let mut bytes = [byte; NodeId::LENGTH];
+        bytes[0] = entity_type as u8;
+        ResourceAddress::new_or_panic(bytes)
+    }
+
+    fn component(entity_type: EntityType, byte: u8) -> ComponentAddress {
+        let mut bytes = [byte; NodeId::LENGTH];
+        bytes[0] = entity_type as u8;
+        ComponentAddress::new_or_panic(bytes)
+    }
+
+    /// Saves on stokenet, then loads on stokenet and mainnet
+    fn round_trip<S: Snapshot + PartialEq + fmt::Debug>(name: &str, value: &S) {
+        let path = path(name);
+        value.save(&path, &NetworkDefinition::stokenet()).unwrap();
+
+        assert_eq!(&S::load(&path, &NetworkDefinition::stokenet()).unwrap(), value);
+        match S::load(&path, &NetworkDefinition::mainnet()) {
+            Err(SnapshotError::NetworkMismatch { expected, found }) => {
+                assert_eq!(expected, "mainnet");
+                assert_eq!(found, "stokenet");
+            }
+            other => panic!("Expected a network mismatch, got {:?}", other),
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn anthic_config_round_trips() {
+        round_trip("config", &AnthicConfig {
+            verify_parent_access_rule: AccessRule::DenyAll,
+            symbol_to_resource: [
+                ("XRD".to_string(), XRD),
+                ("xUSDC".to_string(), resource(EntityType::GlobalFungibleResourceManager, 1)),
+            ].into_iter().collect(),
+            settlement_fee_per_resource: [
+                ("XRD".to_string(), dec!(5)),
+                ("xUSDC".to_string(), dec!("0.100000000000000001")),
+            ].into_iter().collect(),
+            anthic_fee_per_level: vec![
+                AnthicLevelFee { taker_fee: dec!("0.001"), maker_fee: dec!(0) },
+                AnthicLevelFee { taker_fee: dec!("0.0008"), maker_fee: dec!("-0.0001") },
+            ],
+        });
+    }
+
+    #[test]
+    fn instamint_config_round_trips() {
+        round_trip("instamint", &InstamintConfig {
+            customer_badge_resource: resource(EntityType::GlobalNonFungibleResourceManager, 2),
+            instamint_component: component(EntityType::GlobalGenericComponent, 3),
+        });
+    }
+
+    #[test]
+    fn anthic_account_round_trips() {
+        let local_ids = vec![NonFungibleLocalId::integer(1), NonFungibleLocalId::string("badge").unwrap()];
+        round_trip("account", &AnthicAccount::new(component(EntityType::GlobalAccount, 4), local_ids.clone())
+            .select_badge(&local_ids[1])
+            .unwrap());
+        round_trip("account-without-badges", &AnthicAccount::new(component(EntityType::GlobalAccount, 5), vec![]));
+    }
+
+    #[test]
+    fn on_ledger_account_round_trips() {
+        round_trip("on-ledger-account", &OnLedgerAccount {
+            address: component(EntityType::GlobalAccount, 6),
+            balances: [
+                (XRD, dec!("1234.5")),
+                (resource(EntityType::GlobalFungibleResourceManager, 7), Decimal::MAX),
+            ].into_iter().collect(),
+        });
+    }
+
+    #[test]
+    fn snapshots_with_invalid_values_are_rejected() {
+        let network = NetworkDefinition::stokenet();
+        let account = OnLedgerAccount {
+            address: component(EntityType::GlobalAccount, 8),
+            balances: [(XRD, dec!(1))].into_iter().collect(),
+        };
+        let mut repr = account.to_snapshot(&network).unwrap();
+        repr.balances.insert(repr.balances.keys().next().unwrap().clone(), "one".to_string());
+        assert!(matches!(OnLedgerAccount::from_snapshot(repr, &network), Err(SnapshotError::Decode(_))));
+
+        // Addresses are only decoded on the network they were encoded for
+        let repr = account.to_snapshot(&NetworkDefinition::mainnet()).unwrap();
+        assert!(matches!(OnLedgerAccount::from_snapshot(repr, &network), Err(SnapshotError::Decode(_))));
+        // The selected badge must be one of the account's badges
+        let account = AnthicAccount::new(component(EntityType::GlobalAccount, 9), vec![NonFungibleLocalId::integer(1)]);
+        let mut repr = account.to_snapshot(&network).unwrap();
+        repr.instamint_customer_badge_local_id = Some(NonFungibleLocalId::integer(2).to_string());
+        assert!(matches!(AnthicAccount::from_snapshot(repr, &network), Err(SnapshotError::Decode(_))));
+    }
+}
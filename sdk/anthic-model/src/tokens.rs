@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use radix_common::prelude::*;
+
+/// Resources have at most 18 decimal places
+pub const MAX_DIVISIBILITY: u8 = 18;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub symbol: String,
+    pub address: ResourceAddress,
+    /// Number of decimal places an amount of this token may have, `None` if unknown.
+    /// Amounts of tokens with an unknown divisibility cannot be normalized or rounded.
+    pub divisibility: Option<u8>,
+    pub name: Option<String>,
+    pub icon_url: Option<String>,
+}
+
+/// A traded pair of Anthic tokens, prices are in units of `quote` per unit of `base`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TokenPair {
+    pub base: String,
+    pub quote: String,
+}
+
+/// Anthic tokens, looked up by symbol or by resource address
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TokenRegistry {
+    by_symbol: BTreeMap<String, TokenInfo>,
+    by_address: BTreeMap<ResourceAddress, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TokenRegistryError {
+    UnknownSymbol(String),
+    /// The divisibility of the token was not reported by the api
+    UnknownDivisibility(String),
+    /// The amount has more decimal places than the token allows
+    ExceedsDivisibility {
+        symbol: String,
+        amount: Decimal,
+        divisibility: u8,
+    },
+}
+
+impl fmt::Display for TokenRegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenRegistryError::UnknownSymbol(symbol) => write!(f, "Unknown token: {}", symbol),
+            TokenRegistryError::UnknownDivisibility(symbol) => write!(f, "Unknown divisibility of {}", symbol),
+            TokenRegistryError::ExceedsDivisibility { symbol, amount, divisibility } => {
+                write!(f, "{} {} has more than {} decimal places", amount, symbol, divisibility)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TokenRegistryError {}
+
+impl TokenRegistry {
+    pub fn new(tokens: impl IntoIterator<Item = TokenInfo>) -> Self {
+        let mut registry = Self::default();
+        for token in tokens {
+            registry.insert(token);
+        }
+        registry
+    }
+
+    /// A registry without metadata, the divisibility of every token is unknown
+    pub fn from_symbol_to_resource(symbol_to_resource: &HashMap<String, ResourceAddress>) -> Self {
+        Self::new(symbol_to_resource.iter().map(|(symbol, address)| TokenInfo {
+            symbol: symbol.clone(),
+            address: *address,
+            divisibility: None,
+            name: None,
+            icon_url: None,
+        }))
+    }
+
+    /// Replaces any token with the same symbol or the same address
+    pub fn insert(&mut self, token: TokenInfo) {
+        if let Some(previous) = self.by_symbol.get(&token.symbol) {
+            self.by_address.remove(&previous.address);
+        }
+        if let Some(previous_symbol) = self.by_address.insert(token.address, token.symbol.clone()) {
+            if previous_symbol != token.symbol {
+                self.by_symbol.remove(&previous_symbol);
+            }
+        }
+        self.by_symbol.insert(token.symbol.clone(), token);
+    }
+
+    pub fn by_symbol(&self, symbol: &str) -> Option<&TokenInfo> {
+        self.by_symbol.get(symbol)
+    }
+
+    pub fn by_address(&self, address: &ResourceAddress) -> Option<&TokenInfo> {
+        let symbol = self.by_address.get(address)?;
+        self.by_symbol.get(symbol)
+    }
+
+    pub fn address(&self, symbol: &str) -> Option<ResourceAddress> {
+        self.by_symbol(symbol).map(|token| token.address)
+    }
+
+    pub fn symbol(&self, address: &ResourceAddress) -> Option<&str> {
+        self.by_address.get(address).map(|symbol| symbol.as_str())
+    }
+
+    /// Tokens ordered by symbol
+    pub fn iter(&self) -> impl Iterator<Item = &TokenInfo> {
+        self.by_symbol.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_symbol.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_symbol.is_empty()
+    }
+
+    /// The divisibility of a token, failing if the token or its divisibility is unknown
+    pub fn divisibility(&self, symbol: &str) -> Result<u8, TokenRegistryError> {
+        let token = self.by_symbol(symbol).ok_or_else(|| TokenRegistryError::UnknownSymbol(symbol.to_string()))?;
+        token.divisibility.ok_or_else(|| TokenRegistryError::UnknownDivisibility(token.symbol.clone()))
+    }
+
+    /// Returns the amount if it is representable in the token, rejecting amounts finer than its divisibility
+    pub fn normalize_amount(&self, symbol: &str, amount: Decimal) -> Result<Decimal, TokenRegistryError> {
+        let divisibility = self.divisibility(symbol)?;
+        let rounded = amount.checked_round(divisibility, RoundingMode::ToZero);
+        if rounded != Some(amount) {
+            return Err(TokenRegistryError::ExceedsDivisibility {
+                symbol: symbol.to_string(),
+                amount,
+                divisibility,
+            });
+        }
+        Ok(amount)
+    }
+
+    /// Rounds an amount to the divisibility of the token
+    pub fn round_amount(&self, symbol: &str, amount: Decimal, mode: RoundingMode) -> Result<Decimal, TokenRegistryError> {
+        let divisibility = self.divisibility(symbol)?;
+        amount.checked_round(divisibility, mode).ok_or(TokenRegistryError::ExceedsDivisibility {
+            symbol: symbol.to_string(),
+            amount,
+            divisibility,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource(byte: u8) -> ResourceAddress {
+        let mut bytes = [byte; NodeId::LENGTH];
+        bytes[0] = EntityType::GlobalFungibleResourceManager as u8;
+        ResourceAddress::new_or_panic(bytes)
+    }
+
+    fn token(symbol: &str, address: ResourceAddress, divisibility: Option<u8>) -> TokenInfo {
+        TokenInfo {
+            symbol: symbol.to_string(),
+            address,
+            divisibility,
+            name: None,
+            icon_url: None,
+        }
+    }
+
+    fn registry() -> TokenRegistry {
+        TokenRegistry::new([
+            token("xUSDC", resource(1), Some(6)),
+            token("xwBTC", resource(2), Some(8)),
+            token("XRD", resource(3), Some(18)),
+        ])
+    }
+
+    #[test]
+    fn tokens_are_looked_up_by_symbol_and_address() {
+        let registry = registry();
+        assert_eq!(registry.len(), 3);
+        assert_eq!(registry.address("xUSDC"), Some(resource(1)));
+        assert_eq!(registry.symbol(&resource(2)), Some("xwBTC"));
+        assert_eq!(registry.by_address(&resource(3)), registry.by_symbol("XRD"));
+        assert_eq!(registry.by_symbol("USDC"), None);
+        assert_eq!(registry.symbol(&resource(4)), None);
+        let symbols: Vec<_> = registry.iter().map(|token| token.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["XRD", "xUSDC", "xwBTC"]);
+    }
+
+    #[test]
+    fn reinserting_a_symbol_replaces_its_address() {
+        let mut registry = registry();
+        registry.insert(token("xUSDC", resource(4), Some(6)));
+
+        assert_eq!(registry.len(), 3);
+        assert_eq!(registry.address("xUSDC"), Some(resource(4)));
+        assert_eq!(registry.symbol(&resource(1)), None);
+        assert_eq!(registry.by_address(&resource(1)), None);
+    }
+
+    #[test]
+    fn reinserting_an_address_replaces_its_symbol() {
+        let mut registry = registry();
+        registry.insert(token("USDC", resource(1), Some(6)));
+
+        assert_eq!(registry.len(), 3);
+        assert_eq!(registry.symbol(&resource(1)), Some("USDC"));
+        assert_eq!(registry.by_symbol("xUSDC"), None);
+    }
+
+    #[test]
+    fn amounts_finer_than_the_divisibility_are_rejected() {
+        let registry = registry();
+        assert_eq!(registry.normalize_amount("xUSDC", dec!("95.850001")), Ok(dec!("95.850001")));
+        assert_eq!(registry.normalize_amount("xUSDC", dec!("95.8500001")), Err(TokenRegistryError::ExceedsDivisibility {
+            symbol: "xUSDC".to_string(),
+            amount: dec!("95.8500001"),
+            divisibility: 6,
+        }));
+        assert_eq!(registry.normalize_amount("XRD", dec!("0.000000000000000001")), Ok(dec!("0.000000000000000001")));
+        assert_eq!(registry.normalize_amount("USDC", dec!(1)), Err(TokenRegistryError::UnknownSymbol("USDC".to_string())));
+    }
+
+    #[test]
+    fn amounts_are_rounded_to_the_divisibility() {
+        let registry = registry();
+        assert_eq!(registry.round_amount("xwBTC", dec!("0.123456789"), RoundingMode::ToZero), Ok(dec!("0.12345678")));
+        assert_eq!(registry.round_amount("xwBTC", dec!("0.123456789"), RoundingMode::AwayFromZero), Ok(dec!("0.12345679")));
+        assert_eq!(registry.round_amount("xUSDC", dec!(5), RoundingMode::ToZero), Ok(dec!(5)));
+    }
+
+    #[test]
+    fn unknown_divisibilities_are_not_assumed() {
+        let registry = TokenRegistry::from_symbol_to_resource(&[("xUSDC".to_string(), resource(1))].into_iter().collect());
+        assert_eq!(registry.divisibility("xUSDC"), Err(TokenRegistryError::UnknownDivisibility("xUSDC".to_string())));
+        assert!(registry.normalize_amount("xUSDC", dec!(1)).is_err());
+        assert!(registry.round_amount("xUSDC", dec!(1), RoundingMode::ToZero).is_err());
+    }
+}
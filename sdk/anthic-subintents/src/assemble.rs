@@ -0,0 +1,179 @@
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use radix_common::prelude::*;
+use radix_transactions::model::*;
+use radix_transactions::prelude::*;
+use crate::nonce::NonceProvider;
+
+/// Anthic requires subintents to remain valid for at least this many seconds
+pub const MIN_EXPIRY_SECS: i64 = 10;
+
+/// Epochs for which a subintent is valid, starting at the current epoch
+pub const EPOCH_WINDOW: u64 = 2;
+
+/// Wraps a manifest into a subintent which expires `expire_after_secs` from now
+pub fn create_subintent(
+    network_definition: &NetworkDefinition,
+    manifest: SubintentManifestV2,
+    expire_after_secs: i64,
+    cur_epoch: u64,
+    nonce: u64,
+) -> SubintentV2 {
+    create_subintent_with_message(network_definition, manifest, expire_after_secs, cur_epoch, nonce, MessageV2::None)
+}
+
+/// Like [`create_subintent`], attaching a message, e.g. from [`crate::messages::client_order_id_message`]
+pub fn create_subintent_with_message(
+    network_definition: &NetworkDefinition,
+    manifest: SubintentManifestV2,
+    expire_after_secs: i64,
+    cur_epoch: u64,
+    nonce: u64,
+    message: MessageV2,
+) -> SubintentV2 {
+    let (instructions, blobs, children) = manifest.for_intent();
+    let now_secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    let expiry_timestamp_secs = now_secs + expire_after_secs;
+
+    SubintentV2 {
+        intent_core: IntentCoreV2 {
+            header: IntentHeaderV2 {
+                network_id: network_definition.id,
+                start_epoch_inclusive: Epoch::of(cur_epoch),
+                end_epoch_exclusive: Epoch::of(cur_epoch + EPOCH_WINDOW),
+                min_proposer_timestamp_inclusive: None,
+                max_proposer_timestamp_exclusive: Some(Instant::new(expiry_timestamp_secs)),
+                intent_discriminator: nonce,
+            },
+            blobs,
+            message,
+            children,
+            instructions,
+        },
+    }
+}
+
+/// The hash which must be signed by the account owner
+pub fn subintent_hash(subintent: &SubintentV2) -> Result<SubintentHash, String> {
+    let prepared = subintent
+        .prepare(PreparationSettingsV1::latest_ref())
+        .map_err(|err| format!("Prepare error: {:?}", err))?;
+    Ok(prepared.subintent_hash())
+}
+
+/// Creates the signed partial transaction which may be submitted to Anthic
+pub fn create_signed_partial_transaction(
+    subintent: SubintentV2,
+    signatures: Vec<SignatureWithPublicKeyV1>,
+) -> SignedPartialTransactionV2 {
+    SignedPartialTransactionV2 {
+        partial_transaction: PartialTransactionV2 {
+            root_subintent: subintent,
+            non_root_subintents: NonRootSubintentsV2(Default::default()),
+        },
+        root_subintent_signatures: IntentSignaturesV2 {
+            signatures: signatures.into_iter().map(IntentSignatureV1).collect(),
+        },
+        non_root_subintent_signatures: NonRootSubintentSignaturesV2 {
+            by_subintent: Default::default(),
+        },
+    }
+}
+
+/// Assembles subintents with nonces from a [`NonceProvider`], refusing to hand out
+/// a subintent hash twice so that the same subintent is never signed again.
+///
+/// Duplicates are detected in memory, among the subintents of this assembler which have not
+/// yet expired. Uniqueness across restarts relies on the nonce provider, e.g. [`crate::nonce::PersistedCounterNonce`].
+pub struct SubintentAssembler<N> {
+    network: NetworkDefinition,
+    nonces: N,
+    /// Hashes of assembled subintents with their expiry timestamp
+    assembled: Mutex<HashMap<SubintentHash, i64>>,
+}
+
+impl<N: NonceProvider> SubintentAssembler<N> {
+    pub fn new(network: NetworkDefinition, nonces: N) -> Self {
+        Self {
+            network,
+            nonces,
+            assembled: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the subintent together with the hash to sign
+    pub fn assemble(
+        &self,
+        manifest: SubintentManifestV2,
+        expire_after_secs: i64,
+        cur_epoch: u64,
+    ) -> Result<(SubintentV2, SubintentHash), String> {
+        self.assemble_with_message(manifest, expire_after_secs, cur_epoch, MessageV2::None)
+    }
+
+    pub fn assemble_with_message(
+        &self,
+        manifest: SubintentManifestV2,
+        expire_after_secs: i64,
+        cur_epoch: u64,
+        message: MessageV2,
+    ) -> Result<(SubintentV2, SubintentHash), String> {
+        let nonce = self.nonces.next_nonce()?;
+        let subintent = create_subintent_with_message(&self.network, manifest, expire_after_secs, cur_epoch, nonce, message);
+        let hash = subintent_hash(&subintent)?;
+        let expiry_secs = subintent.intent_core.header.max_proposer_timestamp_exclusive
+            .map(|expiry| expiry.seconds_since_unix_epoch)
+            .unwrap_or(i64::MAX);
+
+        let mut assembled = self.assembled.lock().unwrap();
+        // Expired subintents can no longer be committed, so they need not be remembered
+        let now_secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        assembled.retain(|_, expiry| *expiry > now_secs);
+        if assembled.insert(hash, expiry_secs).is_some() {
+            return Err(format!("Duplicate subintent hash {:?}, nonce {} was reused", hash, nonce));
+        }
+        Ok((subintent, hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hands out the same nonce every time, as a misconfigured provider would
+    struct FixedNonce;
+
+    impl NonceProvider for FixedNonce {
+        fn next_nonce(&self) -> Result<u64, String> {
+            Ok(7)
+        }
+    }
+
+    fn manifest() -> SubintentManifestV2 {
+        SubintentManifestV2Builder::new_subintent_v2().yield_to_parent(()).build()
+    }
+
+    #[test]
+    fn reused_nonces_are_refused() {
+        let assembler = SubintentAssembler::new(NetworkDefinition::stokenet(), FixedNonce);
+        assembler.assemble(manifest(), 60, 10).unwrap();
+
+        // The expiry is taken from the clock, which may tick between two assemblies
+        let refused = (0..3).any(|_| {
+            assembler.assemble(manifest(), 60, 10).is_err_and(|err| err.starts_with("Duplicate subintent hash"))
+        });
+        assert!(refused);
+    }
+
+    #[test]
+    fn expired_subintents_are_forgotten() {
+        let assembler = SubintentAssembler::new(NetworkDefinition::stokenet(), FixedNonce);
+
+        for _ in 0..3 {
+            assembler.assemble(manifest(), -1, 10).unwrap();
+        }
+        assembler.assemble(manifest(), 60, 10).unwrap();
+
+        assert_eq!(assembler.assembled.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,257 @@
+use radix_common::prelude::*;
+use radix_transactions::manifest::decompile;
+use radix_transactions::model::*;
+use radix_transactions::prelude::*;
+use serde_json::json;
+use crate::assemble::subintent_hash;
+use crate::validate::{anthic_validate_subintent, AnthicLimitOrderDefinition};
+
+/// Everything which can be learnt from a subintent without the Anthic config,
+/// e.g. to diagnose a rejected order from its hex
+#[derive(Clone, Debug)]
+pub struct SubintentInspection {
+    pub header: InspectedHeader,
+    /// The network of the subintent, derived from its header
+    pub network: NetworkDefinition,
+    /// Set if the subintent is not for the network the caller expected
+    pub network_mismatch: Option<String>,
+    pub subintent_hash: SubintentHash,
+    /// Empty if only the subintent was given
+    pub signers: Vec<InspectedSigner>,
+    /// Decompiled manifest, or the reason it could not be decompiled
+    pub manifest: Result<String, String>,
+    pub validation: Result<AnthicLimitOrderDefinition, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InspectedHeader {
+    pub network_id: u8,
+    pub start_epoch_inclusive: u64,
+    pub end_epoch_exclusive: u64,
+    pub min_proposer_timestamp_inclusive: Option<i64>,
+    pub max_proposer_timestamp_exclusive: Option<i64>,
+    pub nonce: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InspectedSigner {
+    /// `None` if no public key could be recovered from the signature
+    pub public_key: Option<PublicKey>,
+    /// Whether the signature is valid for the subintent hash
+    pub valid: bool,
+}
+
+/// Decodes hex of either a `SignedPartialTransactionV2` or a manifest encoded `SubintentV2`
+pub fn inspect_hex(hex: &str, network: &NetworkDefinition) -> Result<SubintentInspection, String> {
+    let bytes = hex::decode(hex.trim()).map_err(|err| format!("Invalid hex: {}", err))?;
+
+    if let Ok(signed_partial_transaction) = SignedPartialTransactionV2::from_raw(&RawSignedPartialTransaction::from_vec(bytes.clone())) {
+        return inspect_signed_partial_transaction(&signed_partial_transaction, network);
+    }
+    let subintent = manifest_decode::<SubintentV2>(&bytes)
+        .map_err(|_| "Neither a signed partial transaction nor a subintent".to_string())?;
+    inspect_subintent(&subintent, network)
+}
+
+pub fn inspect_signed_partial_transaction(
+    signed_partial_transaction: &SignedPartialTransactionV2,
+    network: &NetworkDefinition,
+) -> Result<SubintentInspection, String> {
+    let mut inspection = inspect_subintent(&signed_partial_transaction.partial_transaction.root_subintent, network)?;
+    inspection.signers = signed_partial_transaction.root_subintent_signatures.signatures.iter()
+        .map(|signature| inspect_signature(&inspection.subintent_hash, &signature.0))
+        .collect();
+    Ok(inspection)
+}
+
+pub fn inspect_subintent(subintent: &SubintentV2, network: &NetworkDefinition) -> Result<SubintentInspection, String> {
+    let header = &subintent.intent_core.header;
+    let header = InspectedHeader {
+        network_id: header.network_id,
+        start_epoch_inclusive: header.start_epoch_inclusive.number(),
+        end_epoch_exclusive: header.end_epoch_exclusive.number(),
+        min_proposer_timestamp_inclusive: header.min_proposer_timestamp_inclusive.map(|t| t.seconds_since_unix_epoch),
+        max_proposer_timestamp_exclusive: header.max_proposer_timestamp_exclusive.map(|t| t.seconds_since_unix_epoch),
+        nonce: header.intent_discriminator,
+    };
+    let subintent_hash = subintent_hash(subintent)?;
+
+    let subintent_network = NetworkDefinition::from_network_id(header.network_id);
+    let network_mismatch = (header.network_id != network.id)
+        .then(|| format!("Subintent is for network {} but {} was expected", header.network_id, network.id));
+    let manifest = SubintentManifestV2::from_intent_core(&subintent.intent_core);
+    let manifest = decompile(&manifest, &subintent_network).map_err(|err| format!("Decompile error: {:?}", err));
+    let validation = anthic_validate_subintent(subintent);
+
+    Ok(SubintentInspection {
+        header,
+        network: subintent_network,
+        network_mismatch,
+        subintent_hash,
+        signers: Vec::new(),
+        manifest,
+        validation,
+    })
+}
+
+fn inspect_signature(hash: &SubintentHash, signature: &SignatureWithPublicKeyV1) -> InspectedSigner {
+    match signature {
+        SignatureWithPublicKeyV1::Secp256k1 { signature } => {
+            let public_key = verify_and_recover_secp256k1(hash, signature);
+            InspectedSigner {
+                valid: public_key.is_some(),
+                public_key: public_key.map(PublicKey::Secp256k1),
+            }
+        }
+        SignatureWithPublicKeyV1::Ed25519 { public_key, signature } => InspectedSigner {
+            public_key: Some(PublicKey::Ed25519(*public_key)),
+            valid: verify_ed25519(hash, public_key, signature),
+        },
+    }
+}
+
+impl SubintentInspection {
+    /// Whether the subintent is a valid Anthic limit order for the expected network, with valid signatures
+    pub fn is_valid(&self) -> bool {
+        self.network_mismatch.is_none() && self.validation.is_ok() && self.signers.iter().all(|signer| signer.valid)
+    }
+
+    /// Addresses and hashes are encoded for the network of the subintent
+    pub fn to_json(&self) -> Result<serde_json::Value, String> {
+        let encoder = AddressBech32Encoder::new(&self.network);
+        let encode = |bytes: &[u8]| encoder.encode(bytes).map_err(|err| format!("Encode error: {:?}", err));
+        let subintent_hash = TransactionHashBech32Encoder::new(&self.network).encode(&self.subintent_hash)
+            .map_err(|err| format!("Encode error: {:?}", err))?;
+        let signers: Vec<_> = self.signers.iter()
+            .map(|signer| json!({
+                "public_key_hex": signer.public_key.map(|key| hex::encode(key.to_vec())),
+                "valid": signer.valid,
+            }))
+            .collect();
+        let validation = match &self.validation {
+            Ok(order) => json!({
+                "valid": true,
+                "account": encode(order.meta.account.as_bytes())?,
+                "sell_resource": encode(order.trade.sell.resource.as_bytes())?,
+                "sell_amount": order.trade.sell.amount.to_string(),
+                "buy_resource": encode(order.trade.buy.resource.as_bytes())?,
+                "buy_amount": order.trade.buy.amount.to_string(),
+                "buy_constraint": format!("{:?}", order.trade.buy_constraint),
+                "sell_non_fungible_ids": order.trade.sell_non_fungible_ids.as_ref()
+                    .map(|ids| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>()),
+                "buy_non_fungible_ids": order.trade.buy_non_fungible_ids.as_ref()
+                    .map(|ids| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>()),
+            }),
+            Err(error) => json!({
+                "valid": false,
+                "error": error,
+            }),
+        };
+
+        Ok(json!({
+            "header": {
+                "network_id": self.header.network_id,
+                "network": self.network.logical_name,
+                "start_epoch_inclusive": self.header.start_epoch_inclusive,
+                "end_epoch_exclusive": self.header.end_epoch_exclusive,
+                "min_proposer_timestamp_inclusive": self.header.min_proposer_timestamp_inclusive,
+                "max_proposer_timestamp_exclusive": self.header.max_proposer_timestamp_exclusive,
+                "nonce": self.header.nonce,
+            },
+            "network_mismatch": self.network_mismatch,
+            "subintent_hash": subintent_hash,
+            "signers": signers,
+            "manifest": self.manifest.as_ref().ok(),
+            "manifest_error": self.manifest.as_ref().err(),
+            "validation": validation,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assemble::{create_signed_partial_transaction, create_subintent};
+    use crate::test_support::*;
+    use crate::AnthicSubintentManifestBuilder;
+
+    fn subintent(network: &NetworkDefinition) -> SubintentV2 {
+        let manifest = AnthicSubintentManifestBuilder::new(config_with(&[("xUSDC", usdc()), ("xwBTC", btc())]))
+            .add_anthic_limit_order_with_options(account(), amount("xUSDC", dec!(100)), amount("xwBTC", dec!("0.001")), dec!("0.1"), dec!("0.1"), &Default::default())
+            .unwrap()
+            .build();
+        create_subintent(network, manifest, 60, 10, 42)
+    }
+
+    fn signed_partial_transaction_hex(subintent: SubintentV2, signatures: Vec<SignatureWithPublicKeyV1>) -> String {
+        create_signed_partial_transaction(subintent, signatures).to_raw().unwrap().to_hex()
+    }
+
+    #[test]
+    fn signed_partial_transactions_are_inspected() {
+        let network = NetworkDefinition::stokenet();
+        let subintent = subintent(&network);
+        let hash = subintent_hash(&subintent).unwrap();
+        let key = Secp256k1PrivateKey::from_u64(1).unwrap();
+        let other_key = Ed25519PrivateKey::from_u64(2).unwrap();
+        let other_hash = SubintentHash(hash!("0000000000000000000000000000000000000000000000000000000000000001"));
+        let signatures = vec![
+            SignatureWithPublicKeyV1::Secp256k1 { signature: key.sign(&hash) },
+            SignatureWithPublicKeyV1::Ed25519 { public_key: other_key.public_key(), signature: other_key.sign(&other_hash) },
+        ];
+
+        let inspection = inspect_hex(&signed_partial_transaction_hex(subintent, signatures), &network).unwrap();
+
+        assert_eq!(inspection.subintent_hash, hash);
+        assert_eq!(inspection.header.network_id, network.id);
+        assert_eq!((inspection.header.start_epoch_inclusive, inspection.header.end_epoch_exclusive), (10, 12));
+        assert_eq!(inspection.header.nonce, 42);
+        assert_eq!(inspection.signers, vec![
+            InspectedSigner { public_key: Some(PublicKey::Secp256k1(key.public_key())), valid: true },
+            InspectedSigner { public_key: Some(PublicKey::Ed25519(other_key.public_key())), valid: false },
+        ]);
+        assert!(inspection.manifest.as_ref().unwrap().contains("YIELD_TO_PARENT"));
+        assert_eq!(inspection.validation.as_ref().unwrap().trade.sell.amount, dec!(100));
+        assert!(!inspection.is_valid());
+
+        let json = inspection.to_json().unwrap();
+        assert_eq!(json["subintent_hash"], TransactionHashBech32Encoder::new(&network).encode(&hash).unwrap());
+        assert_eq!(json["signers"][1]["valid"], false);
+        assert_eq!(json["validation"]["sell_resource"], AddressBech32Encoder::new(&network).encode(usdc().as_bytes()).unwrap());
+        assert_eq!(json["validation"]["buy_amount"], "0.001");
+    }
+
+    #[test]
+    fn bare_subintents_are_inspected() {
+        let network = NetworkDefinition::stokenet();
+        let subintent = subintent(&network);
+        let hex = hex::encode(manifest_encode(&subintent).unwrap());
+
+        let inspection = inspect_hex(&hex, &network).unwrap();
+
+        assert_eq!(inspection.subintent_hash, subintent_hash(&subintent).unwrap());
+        assert!(inspection.signers.is_empty());
+        assert!(inspection.network_mismatch.is_none());
+        assert!(inspection.is_valid());
+        assert_eq!(inspection.to_json().unwrap()["validation"]["valid"], true);
+    }
+
+    #[test]
+    fn network_mismatches_are_reported() {
+        let subintent = subintent(&NetworkDefinition::stokenet());
+
+        let inspection = inspect_hex(&signed_partial_transaction_hex(subintent, vec![]), &NetworkDefinition::mainnet()).unwrap();
+
+        assert_eq!(inspection.network.id, NetworkDefinition::stokenet().id);
+        assert!(inspection.network_mismatch.is_some());
+        assert!(!inspection.is_valid());
+        assert!(inspection.to_json().unwrap()["network_mismatch"].is_string());
+    }
+
+    #[test]
+    fn invalid_hex_is_rejected() {
+        let network = NetworkDefinition::stokenet();
+        assert!(inspect_hex("not hex", &network).unwrap_err().starts_with("Invalid hex"));
+        assert_eq!(inspect_hex("5c2100", &network).unwrap_err(), "Neither a signed partial transaction nor a subintent");
+    }
+}
@@ -0,0 +1,372 @@
+use radix_common::prelude::*;
+use radix_engine_interface::blueprints::account::{
+    AccountWithdrawManifestInput, AccountWithdrawNonFungiblesManifestInput, ACCOUNT_WITHDRAW_IDENT,
+    ACCOUNT_WITHDRAW_NON_FUNGIBLES_IDENT,
+};
+use radix_transactions::model::InstructionV2;
+use radix_transactions::prelude::*;
+use anthic_model::OnLedgerAccount;
+use crate::assemble::subintent_hash;
+use crate::validate::{anthic_validate_subintent, AnthicLimitOrderDefinition, FeeSource};
+use crate::INSTAMINT_MINT_TO_ACCOUNT_IDENT;
+
+/// Amounts withheld from trading for an in-flight subintent
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reservation {
+    /// Amounts per resource which must be available for the subintent to commit: the withdraw
+    /// amounts, including fees, less what the subintent instamints into the account
+    pub amounts: HashMap<ResourceAddress, Decimal>,
+    /// Amounts per resource leaving the account when filled: the sell amount and any fees
+    /// which are not taken from the proceeds
+    pub spent: HashMap<ResourceAddress, Decimal>,
+    /// Amounts per resource instaminted into the account before the order
+    pub minted: HashMap<ResourceAddress, Decimal>,
+    /// The resource received when the subintent is filled
+    pub buy_resource: ResourceAddress,
+    /// Unix timestamp after which the subintent can no longer be committed
+    pub expiry_timestamp_secs: Option<i64>,
+}
+
+/// Tracks the balances of a filler account, less the amounts reserved by in-flight subintents,
+/// so that strategies never commit the same funds twice
+#[derive(Clone, Debug)]
+pub struct InventoryTracker {
+    account: ComponentAddress,
+    balances: HashMap<ResourceAddress, Decimal>,
+    reservations: HashMap<SubintentHash, Reservation>,
+}
+
+impl InventoryTracker {
+    pub fn new(account: &OnLedgerAccount) -> Self {
+        Self {
+            account: account.address,
+            balances: account.balances.clone(),
+            reservations: HashMap::new(),
+        }
+    }
+
+    pub fn account(&self) -> ComponentAddress {
+        self.account
+    }
+
+    /// Replaces balances with freshly loaded ones, keeping reservations
+    pub fn reseed(&mut self, account: &OnLedgerAccount) {
+        self.balances = account.balances.clone();
+    }
+
+    pub fn balance(&self, resource: &ResourceAddress) -> Decimal {
+        self.balances.get(resource).cloned().unwrap_or_default()
+    }
+
+    pub fn reserved(&self, resource: &ResourceAddress) -> Decimal {
+        self.reservations.values()
+            .filter_map(|reservation| reservation.amounts.get(resource))
+            .fold(Decimal::zero(), |total, amount| total + *amount)
+    }
+
+    /// Balance which is not reserved by an in-flight subintent
+    pub fn available(&self, resource: &ResourceAddress) -> Decimal {
+        (self.balance(resource) - self.reserved(resource)).max(Decimal::zero())
+    }
+
+    /// Available balances of all resources, e.g. as inventory for quoting
+    pub fn available_balances(&self) -> HashMap<ResourceAddress, Decimal> {
+        self.balances.keys().map(|resource| (*resource, self.available(resource))).collect()
+    }
+
+    pub fn reservation(&self, hash: &SubintentHash) -> Option<&Reservation> {
+        self.reservations.get(hash)
+    }
+
+    /// Reserves the sell amount and fees of an order, failing if they are not available
+    pub fn reserve(
+        &mut self,
+        hash: SubintentHash,
+        order: &AnthicLimitOrderDefinition,
+        expiry_timestamp_secs: Option<i64>,
+    ) -> Result<(), String> {
+        let spent = spent_amounts(order);
+        self.insert(hash, order, spent.clone(), spent, HashMap::new(), expiry_timestamp_secs)
+    }
+
+    /// Validates a subintent and reserves the amounts it withdraws from the account until it
+    /// expires. Amounts instaminted into the account ahead of the order are credited against
+    /// the withdrawals, so the account only needs to hold the remainder.
+    pub fn reserve_subintent(&mut self, subintent: &SubintentV2) -> Result<SubintentHash, String> {
+        let order = anthic_validate_subintent(subintent)?;
+        let hash = subintent_hash(subintent)?;
+        let expiry_timestamp_secs = subintent.intent_core.header.max_proposer_timestamp_exclusive
+            .map(|instant| instant.seconds_since_unix_epoch);
+        let (withdrawn, minted) = account_flows(&self.account, &subintent.intent_core.instructions.0)?;
+        self.insert(hash, &order, withdrawn, spent_amounts(&order), minted, expiry_timestamp_secs)?;
+        Ok(hash)
+    }
+
+    fn insert(
+        &mut self,
+        hash: SubintentHash,
+        order: &AnthicLimitOrderDefinition,
+        withdrawn: HashMap<ResourceAddress, Decimal>,
+        spent: HashMap<ResourceAddress, Decimal>,
+        minted: HashMap<ResourceAddress, Decimal>,
+        expiry_timestamp_secs: Option<i64>,
+    ) -> Result<(), String> {
+        if order.meta.account != self.account {
+            return Err("Order is for another account".to_string());
+        }
+        if self.reservations.contains_key(&hash) {
+            return Err(format!("Subintent {:?} is already reserved", hash));
+        }
+
+        let amounts: HashMap<ResourceAddress, Decimal> = withdrawn.into_iter()
+            .map(|(resource, amount)| {
+                let minted = minted.get(&resource).cloned().unwrap_or_default();
+                (resource, (amount - minted).max(Decimal::zero()))
+            })
+            .filter(|(_, amount)| amount.is_positive())
+            .collect();
+        for (resource, amount) in &amounts {
+            let available = self.available(resource);
+            if *amount > available {
+                return Err(format!("Insufficient balance of {:?}: need {}, available {}", resource, amount, available));
+            }
+        }
+
+        self.reservations.insert(hash, Reservation {
+            amounts,
+            spent,
+            minted,
+            buy_resource: order.trade.buy.resource,
+            expiry_timestamp_secs,
+        });
+        Ok(())
+    }
+
+    /// Releases the reservation of a subintent which will not be filled
+    pub fn release(&mut self, hash: &SubintentHash) -> Option<Reservation> {
+        self.reservations.remove(hash)
+    }
+
+    /// Releases reservations of subintents expired at `now_secs`, returning their hashes
+    pub fn release_expired(&mut self, now_secs: i64) -> Vec<SubintentHash> {
+        let expired: Vec<SubintentHash> = self.reservations.iter()
+            .filter(|(_, reservation)| reservation.expiry_timestamp_secs.is_some_and(|expiry| expiry <= now_secs))
+            .map(|(hash, _)| *hash)
+            .collect();
+        for hash in &expired {
+            self.reservations.remove(hash);
+        }
+        expired
+    }
+
+    /// Applies a filled subintent: instaminted amounts are credited, the spent amounts debited and
+    /// `bought_amount` credited. `bought_amount` is net, i.e. what was deposited into the account
+    /// after any fees taken from the proceeds. Settlement fee rebates are only reflected on the
+    /// next [`Self::reseed`].
+    pub fn apply_fill(&mut self, hash: &SubintentHash, bought_amount: Decimal) -> Result<(), String> {
+        let reservation = self.reservations.remove(hash)
+            .ok_or_else(|| format!("No reservation for subintent {:?}", hash))?;
+        for (resource, amount) in reservation.minted {
+            *self.balances.entry(resource).or_default() += amount;
+        }
+        for (resource, amount) in reservation.spent {
+            let balance = self.balances.entry(resource).or_default();
+            *balance = (*balance - amount).max(Decimal::zero());
+        }
+        *self.balances.entry(reservation.buy_resource).or_default() += bought_amount;
+        Ok(())
+    }
+}
+
+/// What leaves the account when an order is filled
+fn spent_amounts(order: &AnthicLimitOrderDefinition) -> HashMap<ResourceAddress, Decimal> {
+    let mut spent: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    *spent.entry(order.trade.sell.resource).or_default() += order.trade.sell.amount;
+    if order.fee.source != FeeSource::Proceeds {
+        *spent.entry(order.fee.resource).or_default() += order.fee.anthic_amount + order.fee.settlement_amount;
+    }
+    spent
+}
+
+/// Sums the withdrawals from `account`, and the instamints preceding VerifyParent, per resource
+fn account_flows(
+    account: &ComponentAddress,
+    instructions: &[InstructionV2],
+) -> Result<(HashMap<ResourceAddress, Decimal>, HashMap<ResourceAddress, Decimal>), String> {
+    let mut withdrawn: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    let mut minted: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    let mut verified_parent = false;
+
+    for instruction in instructions {
+        let (address, method_name, args) = match instruction {
+            InstructionV2::VerifyParent(..) => {
+                verified_parent = true;
+                continue;
+            }
+            InstructionV2::CallMethod(CallMethod {
+                                          address: DynamicGlobalAddress::Static(address),
+                                          method_name,
+                                          args,
+                                      }) => (address, method_name, args),
+            _ => continue,
+        };
+        let encoded = manifest_encode(args).map_err(|err| format!("Encode error: {:?}", err))?;
+
+        if address.as_node_id().eq(account.as_node_id()) && method_name.eq(ACCOUNT_WITHDRAW_IDENT) {
+            let withdraw: AccountWithdrawManifestInput = manifest_decode(&encoded)
+                .map_err(|err| format!("Decode error: {:?}", err))?;
+            if let ManifestResourceAddress::Static(resource) = withdraw.resource_address {
+                *withdrawn.entry(resource).or_default() += withdraw.amount;
+            }
+        } else if address.as_node_id().eq(account.as_node_id()) && method_name.eq(ACCOUNT_WITHDRAW_NON_FUNGIBLES_IDENT) {
+            let withdraw: AccountWithdrawNonFungiblesManifestInput = manifest_decode(&encoded)
+                .map_err(|err| format!("Decode error: {:?}", err))?;
+            if let ManifestResourceAddress::Static(resource) = withdraw.resource_address {
+                *withdrawn.entry(resource).or_default() += Decimal::from(withdraw.ids.len());
+            }
+        } else if !verified_parent && method_name.eq(INSTAMINT_MINT_TO_ACCOUNT_IDENT) {
+            let (resource, amount, _): (ManifestResourceAddress, Decimal, ManifestProof) = manifest_decode(&encoded)
+                .map_err(|err| format!("Decode error: {:?}", err))?;
+            if let ManifestResourceAddress::Static(resource) = resource {
+                *minted.entry(resource).or_default() += amount;
+            }
+        }
+    }
+
+    Ok((withdrawn, minted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assemble::create_subintent;
+    use crate::test_support::*;
+    use crate::validate::anthic_validate_manifest;
+    use crate::{AnthicSubintentManifestBuilder, TokenAmount};
+
+    fn tracker(usdc_balance: Decimal) -> InventoryTracker {
+        InventoryTracker::new(&OnLedgerAccount {
+            address: account(),
+            balances: HashMap::from([(usdc(), usdc_balance)]),
+        })
+    }
+
+    fn builder() -> AnthicSubintentManifestBuilder {
+        AnthicSubintentManifestBuilder::new(config_with(&[("xUSDC", usdc()), ("xwBTC", btc())]))
+    }
+
+    /// Sells 100 xUSDC for 0.001 xwBTC, withdrawing 103 xUSDC including fees
+    fn order(builder: AnthicSubintentManifestBuilder) -> SubintentManifestV2 {
+        builder.add_anthic_limit_order(account(), TokenAmount {
+            symbol: "xUSDC".to_string(),
+            amount: dec!(100),
+        }, TokenAmount {
+            symbol: "xwBTC".to_string(),
+            amount: dec!("0.001"),
+        }, dec!(1), dec!(2))
+            .build()
+    }
+
+    fn subintent(manifest: SubintentManifestV2) -> SubintentV2 {
+        create_subintent(&NetworkDefinition::stokenet(), manifest, 60, 1, 1)
+    }
+
+    fn definition() -> AnthicLimitOrderDefinition {
+        anthic_validate_manifest(&order(builder())).unwrap()
+    }
+
+    fn subintent_hash_of(n: u8) -> SubintentHash {
+        SubintentHash(hash([n]))
+    }
+
+    #[test]
+    fn orders_are_reserved_against_the_available_balance() {
+        let mut inventory = tracker(dec!(250));
+
+        inventory.reserve(subintent_hash_of(1), &definition(), None).unwrap();
+        inventory.reserve(subintent_hash_of(2), &definition(), None).unwrap();
+
+        assert_eq!(inventory.reserved(&usdc()), dec!(206));
+        assert_eq!(inventory.available(&usdc()), dec!(44));
+        assert_eq!(
+            inventory.reserve(subintent_hash_of(3), &definition(), None).unwrap_err(),
+            format!("Insufficient balance of {:?}: need 103, available 44", usdc()),
+        );
+        assert!(inventory.reserve(subintent_hash_of(1), &definition(), None).unwrap_err().contains("already reserved"));
+    }
+
+    #[test]
+    fn orders_for_another_account_are_rejected() {
+        let mut inventory = InventoryTracker::new(&OnLedgerAccount {
+            address: ComponentAddress::new_or_panic([EntityType::GlobalAccount as u8; NodeId::LENGTH]),
+            balances: HashMap::from([(usdc(), dec!(1000))]),
+        });
+
+        assert_eq!(inventory.reserve(subintent_hash_of(1), &definition(), None).unwrap_err(), "Order is for another account");
+    }
+
+    #[test]
+    fn reservations_are_released_explicitly_or_on_expiry() {
+        let mut inventory = tracker(dec!(1000));
+        inventory.reserve(subintent_hash_of(1), &definition(), Some(100)).unwrap();
+        inventory.reserve(subintent_hash_of(2), &definition(), Some(200)).unwrap();
+        inventory.reserve(subintent_hash_of(3), &definition(), None).unwrap();
+
+        assert_eq!(inventory.release_expired(99), vec![]);
+        assert_eq!(inventory.release_expired(100), vec![subintent_hash_of(1)]);
+        assert_eq!(inventory.reserved(&usdc()), dec!(206));
+
+        assert!(inventory.release(&subintent_hash_of(3)).is_some());
+        assert!(inventory.release(&subintent_hash_of(3)).is_none());
+        assert_eq!(inventory.available(&usdc()), dec!(897));
+    }
+
+    #[test]
+    fn fills_debit_the_spent_amounts_and_credit_the_proceeds() {
+        let mut inventory = tracker(dec!(250));
+        inventory.reserve(subintent_hash_of(1), &definition(), None).unwrap();
+
+        inventory.apply_fill(&subintent_hash_of(1), dec!("0.001")).unwrap();
+
+        assert_eq!(inventory.balance(&usdc()), dec!(147));
+        assert_eq!(inventory.balance(&btc()), dec!("0.001"));
+        assert_eq!(inventory.reserved(&usdc()), Decimal::zero());
+        assert!(inventory.apply_fill(&subintent_hash_of(1), dec!("0.001")).is_err());
+    }
+
+    #[test]
+    fn every_withdraw_of_a_subintent_is_reserved() {
+        let mut inventory = tracker(dec!(120));
+        let subintent = subintent(order(builder()
+            .builder(|builder| builder.withdraw_from_account(account(), usdc(), dec!(10)))));
+
+        let hash = inventory.reserve_subintent(&subintent).unwrap();
+
+        let reservation = inventory.reservation(&hash).unwrap();
+        assert_eq!(reservation.amounts, HashMap::from([(usdc(), dec!(113))]));
+        assert_eq!(reservation.spent, HashMap::from([(usdc(), dec!(103))]));
+        assert_eq!(inventory.available(&usdc()), dec!(7));
+        assert!(tracker(dec!(112)).reserve_subintent(&subintent).is_err());
+    }
+
+    #[test]
+    fn instamints_ahead_of_the_order_are_credited() {
+        let mut inventory = tracker(dec!(53));
+        let subintent = subintent(order(builder()
+            .instamint_into_account(&instamint_config(), account(), NonFungibleLocalId::integer(1), TokenAmount {
+                symbol: "xUSDC".to_string(),
+                amount: dec!(50),
+            })));
+
+        let hash = inventory.reserve_subintent(&subintent).unwrap();
+
+        let reservation = inventory.reservation(&hash).unwrap();
+        assert_eq!(reservation.amounts, HashMap::from([(usdc(), dec!(53))]));
+        assert_eq!(reservation.minted, HashMap::from([(usdc(), dec!(50))]));
+        assert!(tracker(dec!(52)).reserve_subintent(&subintent).is_err());
+
+        inventory.apply_fill(&hash, dec!("0.001")).unwrap();
+        assert_eq!(inventory.balance(&usdc()), Decimal::zero());
+        assert_eq!(inventory.balance(&btc()), dec!("0.001"));
+    }
+}
@@ -1,8 +1,22 @@
+pub mod assemble;
+pub mod inspect;
+pub mod inventory;
+pub mod lifecycle;
+pub mod messages;
+pub mod nonce;
+pub mod quote;
+pub mod render;
+pub mod repay;
+#[cfg(test)]
+mod test_support;
 pub mod validate;
 
 use radix_common::prelude::*;
 use radix_transactions::prelude::*;
-use anthic_model::{AnthicConfig, InstamintConfig};
+use anthic_model::{AnthicConfig, InstamintConfig, InstamintHeadroom};
+
+/// Method of the instamint component which mints a token into the account of the presented badge
+pub const INSTAMINT_MINT_TO_ACCOUNT_IDENT: &str = "mint_to_account";
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub struct TokenAmount {
@@ -10,6 +24,49 @@ pub struct TokenAmount {
     pub amount: Decimal,
 }
 
+/// One side of a limit order
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub enum OrderLeg {
+    /// A fungible amount, or a count of any non-fungibles of the resource
+    Amount(TokenAmount),
+    /// Specific non-fungibles of the resource
+    NonFungibles {
+        symbol: String,
+        ids: BTreeSet<NonFungibleLocalId>,
+    },
+}
+
+impl OrderLeg {
+    pub fn symbol(&self) -> &str {
+        match self {
+            OrderLeg::Amount(token) => &token.symbol,
+            OrderLeg::NonFungibles { symbol, .. } => symbol,
+        }
+    }
+}
+
+/// What the buy leg of an order must return, relative to its amount
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub enum BuyConstraint {
+    /// At least the buy amount
+    #[default]
+    AtLeast,
+    /// Exactly the buy amount, e.g. for fill-or-kill orders. Non-fungibles named by id are always exact.
+    Exact,
+    /// Between the buy amount and `max`, inclusive
+    AmountRange {
+        max: Decimal,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub struct LimitOrderOptions {
+    pub buy_constraint: BuyConstraint,
+    /// Pays fees in this token instead of the sell token. Fees in the buy token are taken from
+    /// the proceeds, fees in any other token are withdrawn from the account separately.
+    pub fee_symbol: Option<String>,
+}
+
 pub struct AnthicSubintentManifestBuilder {
     config: AnthicConfig,
     builder: SubintentManifestV2Builder,
@@ -43,7 +100,7 @@ impl AnthicSubintentManifestBuilder {
             .with_name_lookup(|builder, lookup| {
                 builder.call_method(
                     instamint_config.instamint_component,
-                    "mint_to_account",
+                    INSTAMINT_MINT_TO_ACCOUNT_IDENT,
                     (resource, to_mint.amount, lookup.proof("instamint-loan-repayment-proof"),),
                 )
             });
@@ -51,32 +108,137 @@ impl AnthicSubintentManifestBuilder {
         self
     }
 
+    /// Like [`Self::instamint_into_account`], but fails if the mint would exceed the remaining
+    /// instamint allowance, which would otherwise only fail on-ledger after the order is filled
+    pub fn try_instamint_into_account(
+        self,
+        instamint_config: &InstamintConfig,
+        headroom: &InstamintHeadroom,
+        account: ComponentAddress,
+        local_id: NonFungibleLocalId,
+        to_mint: TokenAmount,
+    ) -> Result<Self, String> {
+        if !self.config.symbol_to_resource.contains_key(&to_mint.symbol) {
+            return Err(format!("Unknown token: {}", to_mint.symbol));
+        }
+        headroom.check(&to_mint.symbol, to_mint.amount)?;
+        Ok(self.instamint_into_account(instamint_config, account, local_id, to_mint))
+    }
+
     /// Add instructions for an anthic order
     pub fn add_anthic_limit_order(
-        mut self,
+        self,
         account: ComponentAddress,
         sell: TokenAmount,
         buy: TokenAmount,
         settlement_fee_amount: Decimal,
         anthic_fee_amount: Decimal,
     ) -> Self {
-        let sell_resource = self.config.symbol_to_resource.get(&sell.symbol).unwrap().clone();
-        let buy_resource = self.config.symbol_to_resource.get(&buy.symbol).unwrap().clone();
-        let fee_resource = sell_resource;
-        let withdraw_amount = sell.amount + settlement_fee_amount + anthic_fee_amount;
+        self.add_anthic_limit_order_legs(account, OrderLeg::Amount(sell), OrderLeg::Amount(buy), settlement_fee_amount, anthic_fee_amount)
+            .unwrap()
+    }
+
+    /// Add instructions for an anthic order whose legs may be non-fungible.
+    ///
+    /// Fees are paid in the sell resource if it is fungible, and otherwise taken from the
+    /// proceeds of a fungible buy leg.
+    pub fn add_anthic_limit_order_legs(
+        self,
+        account: ComponentAddress,
+        sell: OrderLeg,
+        buy: OrderLeg,
+        settlement_fee_amount: Decimal,
+        anthic_fee_amount: Decimal,
+    ) -> Result<Self, String> {
+        self.add_anthic_limit_order_with_options(account, sell, buy, settlement_fee_amount, anthic_fee_amount, &LimitOrderOptions::default())
+    }
+
+    /// Like [`Self::add_anthic_limit_order_legs`], with options such as the buy constraint and fee token
+    pub fn add_anthic_limit_order_with_options(
+        mut self,
+        account: ComponentAddress,
+        sell: OrderLeg,
+        buy: OrderLeg,
+        settlement_fee_amount: Decimal,
+        anthic_fee_amount: Decimal,
+        options: &LimitOrderOptions,
+    ) -> Result<Self, String> {
+        let sell_resource = self.resource(sell.symbol())?;
+        let buy_resource = self.resource(buy.symbol())?;
+        let fee_resource = match &options.fee_symbol {
+            Some(fee_symbol) => self.resource(fee_symbol)?,
+            None if sell_resource.is_fungible() => sell_resource,
+            None if buy_resource.is_fungible() => buy_resource,
+            None => return Err("Orders between two non-fungible resources have no resource to pay fees in".to_string()),
+        };
+        if !fee_resource.is_fungible() {
+            return Err("Fees must be paid in a fungible resource".to_string());
+        }
+        let fees_from_sell = fee_resource == sell_resource;
+        let fees_from_proceeds = fee_resource == buy_resource;
+
+        // This instruction ensures that the subintent is processed by Anthic before being committed
+        self.builder = self.builder.verify_parent(self.config.verify_parent_access_rule.clone());
+
+        // Withdraw enough to cover the swap, and the fees if paid in the sell resource
+        self.builder = match &sell {
+            OrderLeg::Amount(sell) => {
+                let withdraw_amount = if fees_from_sell {
+                    sell.amount + settlement_fee_amount + anthic_fee_amount
+                } else {
+                    sell.amount
+                };
+                self.builder
+                    .withdraw_from_account(account, sell_resource, withdraw_amount)
+                    .take_from_worktop(sell_resource, sell.amount, "sell")
+            }
+            OrderLeg::NonFungibles { ids, .. } => {
+                if ids.is_empty() {
+                    return Err(format!("No {} non-fungibles to sell", sell.symbol()));
+                }
+                self.builder
+                    .withdraw_non_fungibles_from_account(account, sell_resource, ids.iter().cloned())
+                    .take_non_fungibles_from_worktop(sell_resource, ids.iter().cloned(), "sell")
+            }
+        };
+
+        let buy_constraint = match (&buy, &options.buy_constraint) {
+            (OrderLeg::Amount(buy), BuyConstraint::AtLeast) => ManifestResourceConstraint::AtLeastAmount(buy.amount),
+            (OrderLeg::Amount(buy), BuyConstraint::Exact) => ManifestResourceConstraint::ExactAmount(buy.amount),
+            (OrderLeg::Amount(buy), BuyConstraint::AmountRange { max }) => {
+                if buy.amount.is_negative() || *max < buy.amount {
+                    return Err(format!("Invalid buy range {} to {} {}", buy.amount, max, buy.symbol));
+                }
+                ManifestResourceConstraint::General(GeneralResourceConstraint {
+                    required_ids: IndexSet::new(),
+                    lower_bound: LowerBound::Inclusive(buy.amount),
+                    upper_bound: UpperBound::Inclusive(*max),
+                    allowed_ids: AllowedIds::Any,
+                })
+            }
+            (OrderLeg::NonFungibles { .. }, BuyConstraint::AmountRange { .. }) => {
+                return Err("Non-fungibles named by id cannot be bought in a range".to_string());
+            }
+            (OrderLeg::NonFungibles { ids, .. }, _) => {
+                if ids.is_empty() {
+                    return Err(format!("No {} non-fungibles to buy", buy.symbol()));
+                }
+                ManifestResourceConstraint::ExactNonFungibles(ids.iter().cloned().collect())
+            }
+        };
 
         self.builder = self.builder
-            // This instruction ensures that the subintent is processed by Anthic before being committed
-            .verify_parent(self.config.verify_parent_access_rule.clone())
-            // Withdraw enough to cover fees and the swap
-            .withdraw_from_account(account, sell_resource, withdraw_amount)
             // The following instructions perform the swap
-            .take_from_worktop(sell_resource, sell.amount, "sell")
-            .assert_next_call_returns_only(ManifestResourceConstraints::new().with(
-                buy_resource,
-                ManifestResourceConstraint::AtLeastAmount(buy.amount),
-            ))
-            .with_bucket("sell", |builder, bucket| builder.yield_to_parent((bucket,)))
+            .assert_next_call_returns_only(ManifestResourceConstraints::new().with(buy_resource, buy_constraint))
+            .with_bucket("sell", |builder, bucket| builder.yield_to_parent((bucket,)));
+
+        // Fees in a third resource are withdrawn separately
+        if !fees_from_sell && !fees_from_proceeds {
+            self.builder = self.builder
+                .withdraw_from_account(account, fee_resource, settlement_fee_amount + anthic_fee_amount);
+        }
+
+        self.builder = self.builder
             // The following instructions retrieve the fees
             .take_from_worktop(fee_resource, anthic_fee_amount, "anthic-fee")
             .take_from_worktop(fee_resource, settlement_fee_amount, "settlement-fee")
@@ -93,10 +255,57 @@ impl AnthicSubintentManifestBuilder {
             .deposit_entire_worktop(account)
             .yield_to_parent(());
 
-        self
+        Ok(self)
+    }
+
+    fn resource(&self, symbol: &str) -> Result<ResourceAddress, String> {
+        self.config.symbol_to_resource.get(symbol).cloned()
+            .ok_or_else(|| format!("Unknown token: {}", symbol))
     }
 
     pub fn build(self) -> SubintentManifestV2 {
         self.builder.build()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    /// An allowance of 1000 with 900 xUSDC outstanding
+    fn headroom() -> InstamintHeadroom {
+        InstamintHeadroom {
+            allowance: dec!(1000),
+            outstanding: HashMap::from([("xUSDC".to_string(), dec!(900))]),
+            remaining_per_token: HashMap::from([("xUSDC".to_string(), dec!(100))]),
+        }
+    }
+
+    fn mint(symbol: &str, amount: Decimal) -> Result<SubintentManifestV2, String> {
+        AnthicSubintentManifestBuilder::new(config_with(&[("xUSDC", usdc()), ("xwBTC", btc())]))
+            .try_instamint_into_account(&instamint_config(), &headroom(), account(), NonFungibleLocalId::integer(1), TokenAmount {
+                symbol: symbol.to_string(),
+                amount,
+            })
+            .map(|builder| builder.build())
+    }
+
+    #[test]
+    fn mints_up_to_the_remaining_allowance_are_built() {
+        let manifest = mint("xUSDC", dec!(100)).unwrap();
+        assert_eq!(manifest.instructions.len(), 3);
+        // Without a loan the whole allowance remains
+        assert!(mint("xwBTC", dec!(1000)).is_ok());
+    }
+
+    #[test]
+    fn mints_over_the_remaining_allowance_are_rejected() {
+        assert_eq!(
+            mint("xUSDC", dec!("100.000000000000000001")).unwrap_err(),
+            "Instamint of 100.000000000000000001 xUSDC exceeds remaining allowance of 100 xUSDC",
+        );
+        assert!(mint("xwBTC", dec!("1000.000000000000000001")).is_err());
+        assert_eq!(mint("xETH", dec!(1)).unwrap_err(), "Unknown token: xETH");
+    }
+}
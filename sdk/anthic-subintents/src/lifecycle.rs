@@ -0,0 +1,292 @@
+use radix_common::prelude::*;
+use radix_transactions::prelude::*;
+use crate::inspect::{inspect_subintent, InspectedHeader};
+use crate::validate::{AnthicLimitOrderDefinition, FeeSource};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SubintentState {
+    Signed,
+    Submitted,
+    /// Accepted by Anthic and waiting to be filled
+    Accepted,
+    Filled,
+    Expired,
+    Rejected {
+        reason: String,
+    },
+}
+
+impl SubintentState {
+    /// Filled, expired and rejected subintents never change state again
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, SubintentState::Filled | SubintentState::Expired | SubintentState::Rejected { .. })
+    }
+
+    /// Whether a subintent may move from this state to `next`.
+    /// States only move forward, Signed → Submitted → Accepted, and any state which is not
+    /// terminal may move to a terminal state.
+    pub fn can_transition_to(&self, next: &SubintentState) -> bool {
+        if self.is_terminal() {
+            return false;
+        }
+        if next.is_terminal() {
+            return true;
+        }
+        let rank = |state: &SubintentState| match state {
+            SubintentState::Signed => 0,
+            SubintentState::Submitted => 1,
+            _ => 2,
+        };
+        rank(next) > rank(self)
+    }
+
+    /// Maps an order status reported for a submitted subintent, which is one of the state names.
+    /// Signed is not a valid status as only we know of subintents before they are submitted.
+    pub fn from_api_status(status: &str, message: Option<&str>) -> Result<Self, String> {
+        match status.to_ascii_lowercase().as_str() {
+            "submitted" => Ok(SubintentState::Submitted),
+            "accepted" => Ok(SubintentState::Accepted),
+            "filled" => Ok(SubintentState::Filled),
+            "expired" => Ok(SubintentState::Expired),
+            "rejected" => Ok(SubintentState::Rejected {
+                reason: message.unwrap_or(status).to_string(),
+            }),
+            _ => Err(format!("Unknown subintent status: {}", status)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackedSubintent {
+    pub hash: SubintentHash,
+    pub header: InspectedHeader,
+    pub order: AnthicLimitOrderDefinition,
+    pub state: SubintentState,
+    pub signed_at_secs: i64,
+    pub updated_at_secs: i64,
+}
+
+/// Records signed subintents and follows them until they are filled, expire or are rejected
+#[derive(Clone, Debug, Default)]
+pub struct SubintentTracker {
+    subintents: HashMap<SubintentHash, TrackedSubintent>,
+}
+
+impl SubintentTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a signed subintent, which must be a valid Anthic limit order
+    pub fn track(&mut self, subintent: &SubintentV2, network: &NetworkDefinition, now_secs: i64) -> Result<SubintentHash, String> {
+        let inspection = inspect_subintent(subintent, network)?;
+        if let Some(mismatch) = inspection.network_mismatch {
+            return Err(mismatch);
+        }
+        let order = inspection.validation?;
+        let hash = inspection.subintent_hash;
+        if self.subintents.contains_key(&hash) {
+            return Err(format!("Subintent {:?} is already tracked", hash));
+        }
+
+        self.subintents.insert(hash, TrackedSubintent {
+            hash,
+            header: inspection.header,
+            order,
+            state: SubintentState::Signed,
+            signed_at_secs: now_secs,
+            updated_at_secs: now_secs,
+        });
+        Ok(hash)
+    }
+
+    pub fn get(&self, hash: &SubintentHash) -> Option<&TrackedSubintent> {
+        self.subintents.get(hash)
+    }
+
+    /// Moves a subintent to a new state, see [`SubintentState::can_transition_to`].
+    /// Repeating the current state is a no-op.
+    pub fn transition(&mut self, hash: &SubintentHash, state: SubintentState, now_secs: i64) -> Result<(), String> {
+        let tracked = self.subintents.get_mut(hash)
+            .ok_or_else(|| format!("Subintent {:?} is not tracked", hash))?;
+        if tracked.state == state {
+            return Ok(());
+        }
+        if !tracked.state.can_transition_to(&state) {
+            return Err(format!("Subintent {:?} cannot move from {:?} to {:?}", hash, tracked.state, state));
+        }
+        tracked.state = state;
+        tracked.updated_at_secs = now_secs;
+        Ok(())
+    }
+
+    /// Applies an order status reported for a submitted subintent
+    pub fn apply_api_status(&mut self, hash: &SubintentHash, status: &str, message: Option<&str>, now_secs: i64) -> Result<(), String> {
+        let state = SubintentState::from_api_status(status, message)?;
+        self.transition(hash, state, now_secs)
+    }
+
+    /// Expires outstanding subintents past their expiry timestamp or epoch window,
+    /// returning their hashes
+    pub fn advance(&mut self, now_secs: i64, cur_epoch: Option<u64>) -> Vec<SubintentHash> {
+        let mut expired = Vec::new();
+        for tracked in self.subintents.values_mut() {
+            if tracked.state.is_terminal() {
+                continue;
+            }
+            let timestamp_expired = tracked.header.max_proposer_timestamp_exclusive.is_some_and(|expiry| expiry <= now_secs);
+            let epoch_expired = cur_epoch.is_some_and(|epoch| epoch >= tracked.header.end_epoch_exclusive);
+            if timestamp_expired || epoch_expired {
+                tracked.state = SubintentState::Expired;
+                tracked.updated_at_secs = now_secs;
+                expired.push(tracked.hash);
+            }
+        }
+        expired
+    }
+
+    /// Subintents which may still be filled
+    pub fn outstanding(&self) -> impl Iterator<Item = &TrackedSubintent> {
+        self.subintents.values().filter(|tracked| !tracked.state.is_terminal())
+    }
+
+    pub fn with_state<'a>(&'a self, state: &'a SubintentState) -> impl Iterator<Item = &'a TrackedSubintent> {
+        self.subintents.values().filter(move |tracked| tracked.state == *state)
+    }
+
+    /// The most each resource could be withdrawn by outstanding subintents, including fees
+    pub fn exposure(&self) -> HashMap<ResourceAddress, Decimal> {
+        let mut exposure: HashMap<ResourceAddress, Decimal> = HashMap::new();
+        for tracked in self.outstanding() {
+            let order = &tracked.order;
+            *exposure.entry(order.trade.sell.resource).or_default() += order.trade.sell.amount;
+            if order.fee.source != FeeSource::Proceeds {
+                *exposure.entry(order.fee.resource).or_default() += order.fee.anthic_amount + order.fee.settlement_amount;
+            }
+        }
+        exposure
+    }
+
+    /// Forgets subintents which reached a terminal state before `before_secs`
+    pub fn prune(&mut self, before_secs: i64) {
+        self.subintents.retain(|_, tracked| !tracked.state.is_terminal() || tracked.updated_at_secs >= before_secs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assemble::create_subintent;
+    use crate::test_support::*;
+    use crate::{AnthicSubintentManifestBuilder, LimitOrderOptions};
+
+    fn rejected() -> SubintentState {
+        SubintentState::Rejected { reason: "insufficient funds".to_string() }
+    }
+
+    /// Sells 100 xUSDC for 0.001 xwBTC with 3 in fees, valid for epochs 10 and 11
+    fn subintent(fee_symbol: Option<&str>, nonce: u64) -> SubintentV2 {
+        let manifest = AnthicSubintentManifestBuilder::new(config_with(&[("XRD", xrd()), ("xUSDC", usdc()), ("xwBTC", btc())]))
+            .add_anthic_limit_order_with_options(account(), amount("xUSDC", dec!(100)), amount("xwBTC", dec!("0.001")), dec!(1), dec!(2), &LimitOrderOptions {
+                fee_symbol: fee_symbol.map(str::to_string),
+                ..Default::default()
+            })
+            .unwrap()
+            .build();
+        create_subintent(&NetworkDefinition::stokenet(), manifest, 60, 10, nonce)
+    }
+
+    fn track(tracker: &mut SubintentTracker, subintent: &SubintentV2) -> SubintentHash {
+        tracker.track(subintent, &NetworkDefinition::stokenet(), 0).unwrap()
+    }
+
+    #[test]
+    fn states_only_move_forward() {
+        assert!(SubintentState::Signed.can_transition_to(&SubintentState::Submitted));
+        assert!(SubintentState::Signed.can_transition_to(&SubintentState::Accepted));
+        assert!(SubintentState::Submitted.can_transition_to(&SubintentState::Accepted));
+        assert!(!SubintentState::Accepted.can_transition_to(&SubintentState::Submitted));
+        assert!(!SubintentState::Submitted.can_transition_to(&SubintentState::Signed));
+    }
+
+    #[test]
+    fn any_outstanding_state_may_end() {
+        for state in [SubintentState::Signed, SubintentState::Submitted, SubintentState::Accepted] {
+            assert!(state.can_transition_to(&SubintentState::Filled));
+            assert!(state.can_transition_to(&SubintentState::Expired));
+            assert!(state.can_transition_to(&rejected()));
+        }
+        assert!(!SubintentState::Filled.can_transition_to(&SubintentState::Expired));
+        assert!(!rejected().can_transition_to(&SubintentState::Filled));
+    }
+
+    #[test]
+    fn api_statuses_are_state_names() {
+        assert_eq!(SubintentState::from_api_status("Accepted", None), Ok(SubintentState::Accepted));
+        assert_eq!(SubintentState::from_api_status("rejected", Some("insufficient funds")), Ok(rejected()));
+        assert!(SubintentState::from_api_status("signed", None).is_err());
+        assert!(SubintentState::from_api_status("open", None).is_err());
+    }
+
+    #[test]
+    fn subintents_are_tracked_once_and_only_on_their_network() {
+        let mut tracker = SubintentTracker::new();
+        let subintent = subintent(None, 1);
+
+        let hash = track(&mut tracker, &subintent);
+
+        assert_eq!(tracker.get(&hash).unwrap().state, SubintentState::Signed);
+        assert!(tracker.track(&subintent, &NetworkDefinition::stokenet(), 0).unwrap_err().contains("already tracked"));
+        assert!(SubintentTracker::new().track(&subintent, &NetworkDefinition::mainnet(), 0).is_err());
+    }
+
+    #[test]
+    fn subintents_move_through_api_statuses() {
+        let mut tracker = SubintentTracker::new();
+        let hash = track(&mut tracker, &subintent(None, 1));
+
+        tracker.transition(&hash, SubintentState::Submitted, 1).unwrap();
+        tracker.apply_api_status(&hash, "accepted", None, 2).unwrap();
+        tracker.apply_api_status(&hash, "accepted", None, 3).unwrap();
+        assert_eq!(tracker.get(&hash).unwrap().updated_at_secs, 2);
+
+        tracker.apply_api_status(&hash, "filled", None, 4).unwrap();
+        assert!(tracker.transition(&hash, SubintentState::Expired, 5).is_err());
+        assert_eq!(tracker.with_state(&SubintentState::Filled).count(), 1);
+        assert_eq!(tracker.outstanding().count(), 0);
+    }
+
+    #[test]
+    fn subintents_expire_after_their_timestamp_or_epoch_window() {
+        let mut tracker = SubintentTracker::new();
+        let hash = track(&mut tracker, &subintent(None, 1));
+        let expiry = tracker.get(&hash).unwrap().header.max_proposer_timestamp_exclusive.unwrap();
+
+        assert!(tracker.advance(expiry - 1, Some(11)).is_empty());
+        assert_eq!(tracker.advance(expiry, None), vec![hash]);
+        assert_eq!(tracker.get(&hash).unwrap().state, SubintentState::Expired);
+        assert!(tracker.advance(expiry, None).is_empty());
+
+        let hash = track(&mut tracker, &subintent(None, 2));
+        assert_eq!(tracker.advance(0, Some(12)), vec![hash]);
+    }
+
+    #[test]
+    fn exposure_totals_outstanding_withdrawals() {
+        let mut tracker = SubintentTracker::new();
+        let filled = track(&mut tracker, &subintent(None, 1));
+        track(&mut tracker, &subintent(Some("XRD"), 2));
+        // Fees taken from the proceeds are never withdrawn
+        track(&mut tracker, &subintent(Some("xwBTC"), 3));
+
+        assert_eq!(tracker.exposure(), HashMap::from([(usdc(), dec!(303)), (xrd(), dec!(3))]));
+
+        tracker.transition(&filled, SubintentState::Filled, 10).unwrap();
+        assert_eq!(tracker.exposure(), HashMap::from([(usdc(), dec!(200)), (xrd(), dec!(3))]));
+
+        tracker.prune(10);
+        assert!(tracker.get(&filled).is_some());
+        tracker.prune(11);
+        assert!(tracker.get(&filled).is_none());
+    }
+}
@@ -0,0 +1,336 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use aes_kw::KekAes256;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::{clamp_integer, Scalar};
+use hkdf::Hkdf;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use rand::RngCore;
+use radix_common::prelude::*;
+use radix_transactions::model::*;
+use radix_transactions::prelude::*;
+use sha2::{Digest, Sha256, Sha512};
+
+/// Mime type of plaintext messages carrying a client order id
+pub const CLIENT_ORDER_ID_MIME_TYPE: &str = "text/x-anthic-client-order-id";
+
+/// A plaintext message carrying a client order id, to correlate orders across systems
+pub fn client_order_id_message(client_order_id: &str) -> MessageV2 {
+    plaintext_message(CLIENT_ORDER_ID_MIME_TYPE, client_order_id)
+}
+
+pub fn plaintext_message(mime_type: &str, text: &str) -> MessageV2 {
+    MessageV2::Plaintext(PlaintextMessageV1 {
+        mime_type: mime_type.to_string(),
+        message: MessageContentsV1::String(text.to_string()),
+    })
+}
+
+/// Encrypts plaintext messages to a set of public keys, in the shape of an [`EncryptedMessageV2`]
+pub trait MessageEncryptor {
+    fn encrypt(&self, message: &PlaintextMessageV1, recipients: &[PublicKey]) -> Result<EncryptedMessageV2, String>;
+}
+
+/// Length of the AES-GCM nonce which starts an encrypted payload
+const NONCE_LENGTH: usize = 12;
+
+/// Prefixes the HKDF info of key encryption keys, so they are never shared with another scheme
+const KEK_INFO: &[u8] = b"anthic-message-key-wrap-v1";
+
+/// Encrypts messages for Ed25519 and Secp256k1 recipients:
+///
+/// * the manifest encoded message is encrypted with a random 256-bit key using AES-256-GCM,
+///   the payload being the 12 byte nonce followed by the ciphertext and tag
+/// * an ephemeral key is generated per curve, and the ECDH shared secret with each recipient
+///   is expanded with HKDF-SHA256 into a key encryption key, which wraps the message key with AES-KW.
+///   The ephemeral public key is the HKDF salt and the recipient public key is part of its info.
+/// * recipients are identified by the fingerprint of their public key, the last 8 bytes of its Blake2b-256 hash
+///
+/// This scheme is specific to this SDK and its key derivation differs from that of Radix wallets,
+/// so only [`decrypt_message`] can reverse it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AesGcmMessageEncryptor;
+
+impl MessageEncryptor for AesGcmMessageEncryptor {
+    fn encrypt(&self, message: &PlaintextMessageV1, recipients: &[PublicKey]) -> Result<EncryptedMessageV2, String> {
+        let mut rng = rand::thread_rng();
+        let mut message_key = [0u8; 32];
+        rng.fill_bytes(&mut message_key);
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rng.fill_bytes(&mut nonce);
+
+        let plaintext = manifest_encode(message).map_err(|err| format!("Cannot encode message: {:?}", err))?;
+        let ciphertext = Aes256Gcm::new(&message_key.into())
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| "Cannot encrypt message".to_string())?;
+
+        let mut decryptors_by_curve = IndexMap::default();
+
+        let ed25519_recipients: Vec<_> = recipients.iter()
+            .filter_map(|recipient| match recipient {
+                PublicKey::Ed25519(key) => Some(key),
+                PublicKey::Secp256k1(_) => None,
+            })
+            .collect();
+        if !ed25519_recipients.is_empty() {
+            let mut wide = [0u8; 64];
+            rng.fill_bytes(&mut wide);
+            let ephemeral = Scalar::from_bytes_mod_order_wide(&wide);
+            let ephemeral_public_key = EdwardsPoint::mul_base(&ephemeral).compress().to_bytes();
+            let decryptors = ed25519_recipients.into_iter()
+                .map(|recipient| {
+                    let shared_secret = ed25519_shared_secret(&ephemeral, &recipient.0)?;
+                    let kek = kek(&shared_secret, &ephemeral_public_key, &recipient.0)?;
+                    Ok((fingerprint(&recipient.0), wrap_key(&kek, &message_key)?))
+                })
+                .collect::<Result<_, String>>()?;
+            decryptors_by_curve.insert(CurveType::Ed25519, DecryptorsByCurveV2::Ed25519 {
+                dh_ephemeral_public_key: Ed25519PublicKey(ephemeral_public_key),
+                decryptors,
+            });
+        }
+
+        let secp256k1_recipients: Vec<_> = recipients.iter()
+            .filter_map(|recipient| match recipient {
+                PublicKey::Secp256k1(key) => Some(key),
+                PublicKey::Ed25519(_) => None,
+            })
+            .collect();
+        if !secp256k1_recipients.is_empty() {
+            let ephemeral = k256::NonZeroScalar::random(&mut rng);
+            let ephemeral_public_key = k256::PublicKey::from_secret_scalar(&ephemeral).to_encoded_point(true);
+            let dh_ephemeral_public_key = Secp256k1PublicKey::try_from(ephemeral_public_key.as_bytes())
+                .map_err(|err| format!("Invalid ephemeral key: {:?}", err))?;
+            let decryptors = secp256k1_recipients.into_iter()
+                .map(|recipient| {
+                    let shared_secret = secp256k1_shared_secret(&ephemeral, &recipient.0)?;
+                    let kek = kek(&shared_secret, &dh_ephemeral_public_key.0, &recipient.0)?;
+                    Ok((fingerprint(&recipient.0), wrap_key(&kek, &message_key)?))
+                })
+                .collect::<Result<_, String>>()?;
+            decryptors_by_curve.insert(CurveType::Secp256k1, DecryptorsByCurveV2::Secp256k1 {
+                dh_ephemeral_public_key,
+                decryptors,
+            });
+        }
+
+        Ok(EncryptedMessageV2 {
+            encrypted: AesGcmPayload([nonce.as_slice(), &ciphertext].concat()),
+            decryptors_by_curve,
+        })
+    }
+}
+
+/// Decrypts a message encrypted by [`AesGcmMessageEncryptor`] to the public key of `private_key`
+pub fn decrypt_message(message: &EncryptedMessageV2, private_key: &PrivateKey) -> Result<PlaintextMessageV1, String> {
+    let not_a_recipient = || "The message is not encrypted to this key".to_string();
+    let (kek, wrapped_key) = match private_key {
+        PrivateKey::Ed25519(private_key) => {
+            let public_key = private_key.public_key();
+            let Some(DecryptorsByCurveV2::Ed25519 { dh_ephemeral_public_key, decryptors }) = message.decryptors_by_curve.get(&CurveType::Ed25519) else {
+                return Err(not_a_recipient());
+            };
+            let wrapped_key = decryptors.get(&fingerprint(&public_key.0)).ok_or_else(not_a_recipient)?;
+            let hash = Sha512::digest(private_key.to_bytes());
+            let mut scalar_bytes = [0u8; 32];
+            scalar_bytes.copy_from_slice(&hash[..32]);
+            let scalar = Scalar::from_bytes_mod_order(clamp_integer(scalar_bytes));
+            let shared_secret = ed25519_shared_secret(&scalar, &dh_ephemeral_public_key.0)?;
+            (kek(&shared_secret, &dh_ephemeral_public_key.0, &public_key.0)?, wrapped_key)
+        }
+        PrivateKey::Secp256k1(private_key) => {
+            let public_key = private_key.public_key();
+            let Some(DecryptorsByCurveV2::Secp256k1 { dh_ephemeral_public_key, decryptors }) = message.decryptors_by_curve.get(&CurveType::Secp256k1) else {
+                return Err(not_a_recipient());
+            };
+            let wrapped_key = decryptors.get(&fingerprint(&public_key.0)).ok_or_else(not_a_recipient)?;
+            let scalar = k256::NonZeroScalar::try_from(private_key.to_bytes().as_slice())
+                .map_err(|_| "Invalid private key".to_string())?;
+            let shared_secret = secp256k1_shared_secret(&scalar, &dh_ephemeral_public_key.0)?;
+            (kek(&shared_secret, &dh_ephemeral_public_key.0, &public_key.0)?, wrapped_key)
+        }
+    };
+
+    let mut message_key = [0u8; 32];
+    kek.unwrap(&wrapped_key.0, &mut message_key)
+        .map_err(|_| "Cannot unwrap the message key".to_string())?;
+
+    let payload = &message.encrypted.0;
+    if payload.len() < NONCE_LENGTH {
+        return Err("Encrypted payload is too short".to_string());
+    }
+    let (nonce, ciphertext) = payload.split_at(NONCE_LENGTH);
+    let plaintext = Aes256Gcm::new(&message_key.into())
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Cannot decrypt message".to_string())?;
+    manifest_decode(&plaintext).map_err(|err| format!("Cannot decode message: {:?}", err))
+}
+
+/// The x25519 form of the product of an ed25519 scalar and an ed25519 public key
+fn ed25519_shared_secret(scalar: &Scalar, public_key: &[u8; 32]) -> Result<[u8; 32], String> {
+    let point = CompressedEdwardsY(*public_key).decompress()
+        .filter(|point| !point.is_small_order())
+        .ok_or("Invalid Ed25519 public key")?;
+    Ok((scalar * point).to_montgomery().to_bytes())
+}
+
+fn secp256k1_shared_secret(scalar: &k256::NonZeroScalar, public_key: &[u8]) -> Result<[u8; 32], String> {
+    let public_key = k256::PublicKey::from_sec1_bytes(public_key).map_err(|_| "Invalid Secp256k1 public key")?;
+    let shared_secret = k256::ecdh::diffie_hellman(scalar, public_key.as_affine());
+    Ok((*shared_secret.raw_secret_bytes()).into())
+}
+
+/// The key encryption key of one recipient, bound to the ephemeral and recipient public keys
+/// so that a wrapped key cannot be moved to another recipient or ephemeral key
+fn kek(shared_secret: &[u8; 32], ephemeral_public_key: &[u8], recipient_public_key: &[u8]) -> Result<KekAes256, String> {
+    let mut kek = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(ephemeral_public_key), shared_secret)
+        .expand(&[KEK_INFO, recipient_public_key].concat(), &mut kek)
+        .map_err(|_| "Cannot derive the key encryption key".to_string())?;
+    Ok(KekAes256::from(kek))
+}
+
+fn wrap_key(kek: &KekAes256, message_key: &[u8; 32]) -> Result<AesWrapped256BitKey, String> {
+    let mut wrapped = [0u8; 40];
+    kek.wrap(message_key, &mut wrapped).map_err(|_| "Cannot wrap the message key".to_string())?;
+    Ok(AesWrapped256BitKey(wrapped))
+}
+
+fn fingerprint(public_key: &[u8]) -> PublicKeyFingerprint {
+    let hash = hash(public_key);
+    let mut fingerprint = [0u8; 8];
+    fingerprint.copy_from_slice(&hash.0[24..]);
+    PublicKeyFingerprint(fingerprint)
+}
+
+/// A client order id which only `recipients` can read
+pub fn encrypted_client_order_id_message(
+    encryptor: &impl MessageEncryptor,
+    client_order_id: &str,
+    recipients: &[PublicKey],
+) -> Result<MessageV2, String> {
+    if recipients.is_empty() {
+        return Err("An encrypted message needs at least one recipient".to_string());
+    }
+    let plaintext = PlaintextMessageV1 {
+        mime_type: CLIENT_ORDER_ID_MIME_TYPE.to_string(),
+        message: MessageContentsV1::String(client_order_id.to_string()),
+    };
+    encryptor.encrypt(&plaintext, recipients).map(MessageV2::Encrypted)
+}
+
+/// The message attached to a subintent, as reported by the validator
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub enum SubintentMessage {
+    #[default]
+    None,
+    ClientOrderId(String),
+    Plaintext {
+        mime_type: String,
+        text: Option<String>,
+        /// Set instead of `text` for binary contents
+        bytes: Option<Vec<u8>>,
+    },
+    /// Only the recipients can decrypt the message
+    Encrypted {
+        /// Hex encoded fingerprints of the public keys able to decrypt
+        recipient_fingerprints: Vec<String>,
+    },
+}
+
+impl SubintentMessage {
+    pub fn client_order_id(&self) -> Option<&str> {
+        match self {
+            SubintentMessage::ClientOrderId(id) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+pub fn extract_message(message: &MessageV2) -> SubintentMessage {
+    match message {
+        MessageV2::None => SubintentMessage::None,
+        MessageV2::Plaintext(plaintext) => match &plaintext.message {
+            MessageContentsV1::String(text) if plaintext.mime_type == CLIENT_ORDER_ID_MIME_TYPE => {
+                SubintentMessage::ClientOrderId(text.clone())
+            }
+            MessageContentsV1::String(text) => SubintentMessage::Plaintext {
+                mime_type: plaintext.mime_type.clone(),
+                text: Some(text.clone()),
+                bytes: None,
+            },
+            MessageContentsV1::Bytes(bytes) => SubintentMessage::Plaintext {
+                mime_type: plaintext.mime_type.clone(),
+                text: None,
+                bytes: Some(bytes.clone()),
+            },
+        },
+        MessageV2::Encrypted(encrypted) => {
+            let recipient_fingerprints = encrypted.decryptors_by_curve.values()
+                .flat_map(|decryptors| match decryptors {
+                    DecryptorsByCurveV2::Ed25519 { decryptors, .. } => decryptors.keys().collect::<Vec<_>>(),
+                    DecryptorsByCurveV2::Secp256k1 { decryptors, .. } => decryptors.keys().collect::<Vec<_>>(),
+                })
+                .map(|fingerprint| hex::encode(fingerprint.0))
+                .collect();
+            SubintentMessage::Encrypted {
+                recipient_fingerprints,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order_id() -> PlaintextMessageV1 {
+        PlaintextMessageV1 {
+            mime_type: CLIENT_ORDER_ID_MIME_TYPE.to_string(),
+            message: MessageContentsV1::String("order-42".to_string()),
+        }
+    }
+
+    #[test]
+    fn encrypted_messages_decrypt_for_each_recipient() {
+        let ed25519 = Ed25519PrivateKey::from_u64(1).unwrap();
+        let secp256k1 = Secp256k1PrivateKey::from_u64(2).unwrap();
+        let recipients = [ed25519.public_key().into(), secp256k1.public_key().into()];
+        let encrypted = AesGcmMessageEncryptor.encrypt(&order_id(), &recipients).unwrap();
+
+        assert_eq!(decrypt_message(&encrypted, &PrivateKey::Ed25519(ed25519)), Ok(order_id()));
+        assert_eq!(decrypt_message(&encrypted, &PrivateKey::Secp256k1(secp256k1)), Ok(order_id()));
+    }
+
+    #[test]
+    fn other_keys_cannot_decrypt() {
+        let recipient = Ed25519PrivateKey::from_u64(1).unwrap();
+        let encrypted = AesGcmMessageEncryptor.encrypt(&order_id(), &[recipient.public_key().into()]).unwrap();
+
+        assert!(decrypt_message(&encrypted, &PrivateKey::Ed25519(Ed25519PrivateKey::from_u64(3).unwrap())).is_err());
+        assert!(decrypt_message(&encrypted, &PrivateKey::Secp256k1(Secp256k1PrivateKey::from_u64(1).unwrap())).is_err());
+    }
+
+    #[test]
+    fn tampered_payloads_are_rejected() {
+        let recipient = Secp256k1PrivateKey::from_u64(1).unwrap();
+        let mut encrypted = AesGcmMessageEncryptor.encrypt(&order_id(), &[recipient.public_key().into()]).unwrap();
+        let last = encrypted.encrypted.0.len() - 1;
+        encrypted.encrypted.0[last] ^= 1;
+
+        assert!(decrypt_message(&encrypted, &PrivateKey::Secp256k1(recipient)).is_err());
+    }
+
+    #[test]
+    fn key_encryption_keys_are_bound_to_both_public_keys() {
+        let shared_secret = [1u8; 32];
+        let message_key = [2u8; 32];
+        let wrapped = |ephemeral_public_key: &[u8], recipient_public_key: &[u8]| {
+            wrap_key(&kek(&shared_secret, ephemeral_public_key, recipient_public_key).unwrap(), &message_key).unwrap()
+        };
+
+        assert_eq!(wrapped(&[3; 32], &[4; 32]), wrapped(&[3; 32], &[4; 32]));
+        assert_ne!(wrapped(&[3; 32], &[4; 32]), wrapped(&[5; 32], &[4; 32]));
+        assert_ne!(wrapped(&[3; 32], &[4; 32]), wrapped(&[3; 32], &[5; 32]));
+    }
+}
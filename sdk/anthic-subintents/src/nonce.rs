@@ -0,0 +1,182 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use fs2::FileExt;
+use rand::RngCore;
+
+/// Supplies the `intent_discriminator` of new subintents
+pub trait NonceProvider: Send + Sync {
+    fn next_nonce(&self) -> Result<u64, String>;
+}
+
+/// Random nonces, unique with high probability only
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RandomNonce;
+
+impl NonceProvider for RandomNonce {
+    fn next_nonce(&self) -> Result<u64, String> {
+        Ok(rand::thread_rng().next_u64())
+    }
+}
+
+/// A counter persisted to a file before each nonce is handed out, so nonces never repeat across restarts.
+///
+/// The counter is locked for as long as the provider is alive, so a second process
+/// opening the same file fails rather than handing out the same nonces.
+#[derive(Debug)]
+pub struct PersistedCounterNonce {
+    path: PathBuf,
+    /// Holds the exclusive lock, released when dropped
+    _lock: File,
+    last: Mutex<u64>,
+}
+
+impl PersistedCounterNonce {
+    /// Continues from the counter stored at `path`, starting from zero if the file does not exist.
+    /// Fails if another provider holds the counter.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        // The counter itself is replaced on each write, so the lock is taken on a sibling file
+        let lock_path = with_suffix(&path, ".lock");
+        let lock = OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path)
+            .map_err(|err| format!("Cannot open {}: {}", lock_path.display(), err))?;
+        lock.try_lock_exclusive()
+            .map_err(|err| format!("Nonce counter {} is in use: {}", path.display(), err))?;
+
+        let last = match fs::read_to_string(&path) {
+            Ok(contents) => contents.trim().parse::<u64>()
+                .map_err(|err| format!("Invalid nonce counter in {}: {}", path.display(), err))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(err) => return Err(format!("Cannot read {}: {}", path.display(), err)),
+        };
+        Ok(Self {
+            path,
+            _lock: lock,
+            last: Mutex::new(last),
+        })
+    }
+}
+
+impl NonceProvider for PersistedCounterNonce {
+    fn next_nonce(&self) -> Result<u64, String> {
+        let mut last = self.last.lock().unwrap();
+        let next = last.checked_add(1).ok_or("Nonce counter exhausted")?;
+
+        // Write, sync then rename, so a crash never leaves a truncated or stale counter behind
+        let tmp = with_suffix(&self.path, ".tmp");
+        let write_tmp = || {
+            let mut file = File::create(&tmp)?;
+            file.write_all(next.to_string().as_bytes())?;
+            file.sync_all()
+        };
+        write_tmp().map_err(|err| format!("Cannot write {}: {}", tmp.display(), err))?;
+        fs::rename(&tmp, &self.path).map_err(|err| format!("Cannot write {}: {}", self.path.display(), err))?;
+        sync_parent_dir(&self.path).map_err(|err| format!("Cannot sync {}: {}", self.path.display(), err))?;
+
+        *last = next;
+        Ok(next)
+    }
+}
+
+/// Appends to the whole file name, as replacing the extension could turn the counter path into
+/// that of its own lock or temporary file, e.g. for a counter at `nonce.lock`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Makes a rename durable, directories cannot be opened for syncing on windows
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => File::open(dir)?.sync_all(),
+        _ => File::open(".")?.sync_all(),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Nanoseconds since the unix epoch, strictly increasing within the process
+#[derive(Debug, Default)]
+pub struct TimeBasedNonce {
+    last: Mutex<u64>,
+}
+
+impl TimeBasedNonce {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl NonceProvider for TimeBasedNonce {
+    fn next_nonce(&self) -> Result<u64, String> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64;
+        let mut last = self.last.lock().unwrap();
+        let next = now.max(*last + 1);
+        *last = next;
+        Ok(next)
+    }
+}
+
+impl<T: NonceProvider + ?Sized> NonceProvider for Box<T> {
+    fn next_nonce(&self) -> Result<u64, String> {
+        (**self).next_nonce()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter_path(name: &str) -> PathBuf {
+        counter_path_with_extension(name, "counter")
+    }
+
+    fn counter_path_with_extension(name: &str, extension: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("anthic-nonce-{}-{}.{}", name, std::process::id(), extension));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn persisted_counter_continues_after_reopening() {
+        let path = counter_path("reopen");
+        {
+            let nonces = PersistedCounterNonce::open(&path).unwrap();
+            assert_eq!(nonces.next_nonce(), Ok(1));
+            assert_eq!(nonces.next_nonce(), Ok(2));
+        }
+        let nonces = PersistedCounterNonce::open(&path).unwrap();
+        assert_eq!(nonces.next_nonce(), Ok(3));
+    }
+
+    #[test]
+    fn persisted_counter_is_exclusive() {
+        let path = counter_path("exclusive");
+        let nonces = PersistedCounterNonce::open(&path).unwrap();
+        assert!(PersistedCounterNonce::open(&path).is_err());
+        drop(nonces);
+        assert!(PersistedCounterNonce::open(&path).is_ok());
+    }
+
+    #[test]
+    fn lock_and_temporary_files_never_replace_the_counter() {
+        for extension in ["lock", "tmp"] {
+            let path = counter_path_with_extension("suffix", extension);
+            {
+                let nonces = PersistedCounterNonce::open(&path).unwrap();
+                assert_eq!(nonces.next_nonce(), Ok(1));
+                assert_eq!(nonces.next_nonce(), Ok(2));
+                assert!(PersistedCounterNonce::open(&path).is_err());
+            }
+            assert_eq!(fs::read_to_string(&path).unwrap(), "2");
+            assert_eq!(PersistedCounterNonce::open(&path).unwrap().next_nonce(), Ok(3));
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use radix_common::prelude::*;
+use anthic_model::{AnthicAccount, AnthicConfig, InstamintConfig, InstamintHeadroom, TokenPair, TokenRegistry};
+use crate::validate::AnthicLimitOrderDefinition;
+use crate::{AnthicSubintentManifestBuilder, TokenAmount};
+
+/// Pricing inputs for filling user orders
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuoteParams {
+    /// The pair of the user order, which sets the asset profit is measured in
+    pub pair: TokenPair,
+    /// Units of the quote token for one unit of the base token, e.g. a mid price from a reference venue
+    pub reference_price: Decimal,
+    /// Minimum profit as a fraction of the value received
+    pub spread: Decimal,
+    /// Our Anthic fee level, which sets the maker fee
+    pub level: usize,
+}
+
+/// The parameters of a subintent filling a user order.
+///
+/// The fill inverts the user order: we sell what the user buys and buy what the user sells.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FillQuote {
+    pub sell: TokenAmount,
+    pub buy: TokenAmount,
+    pub settlement_fee_amount: Decimal,
+    pub anthic_fee_amount: Decimal,
+    /// Set if inventory does not cover the sell amount and fees
+    pub instamint: Option<TokenAmount>,
+    /// Profit at the reference price, in units of the quote token of the pair
+    pub expected_profit: Decimal,
+}
+
+/// Quotes a fill of `user_order`, or fails if it is not profitable by at least the spread
+/// or cannot be covered by inventory and, if given, instamint headroom.
+///
+/// `inventory` is as in `OnLedgerAccount::balances`.
+pub fn quote_fill(
+    config: &AnthicConfig,
+    user_order: &AnthicLimitOrderDefinition,
+    params: &QuoteParams,
+    inventory: &HashMap<ResourceAddress, Decimal>,
+    instamint_headroom: Option<&InstamintHeadroom>,
+) -> Result<FillQuote, String> {
+    if user_order.trade.sell_non_fungible_ids.is_some() || user_order.trade.buy_non_fungible_ids.is_some() {
+        return Err("Orders for specific non-fungibles cannot be quoted".to_string());
+    }
+
+    let tokens = TokenRegistry::from_symbol_to_resource(&config.symbol_to_resource);
+    let sell = TokenAmount {
+        symbol: symbol(&tokens, &user_order.trade.buy.resource)?,
+        amount: user_order.trade.buy.amount,
+    };
+    let buy = TokenAmount {
+        symbol: symbol(&tokens, &user_order.trade.sell.resource)?,
+        amount: user_order.trade.sell.amount,
+    };
+
+    let settlement_fee_amount = *config.settlement_fee_per_resource.get(&sell.symbol)
+        .ok_or_else(|| format!("No settlement fee for {}", sell.symbol))?;
+    let maker_fee = config.anthic_fee_per_level.get(params.level)
+        .ok_or_else(|| format!("No anthic fee for level {}", params.level))?
+        .maker_fee;
+    let anthic_fee_amount = sell.amount * maker_fee;
+
+    let cost = sell.amount + settlement_fee_amount + anthic_fee_amount;
+    // Both sides valued in the quote token
+    let (cost_in_quote, value_received) = if sell.symbol == params.pair.quote && buy.symbol == params.pair.base {
+        (cost, buy.amount * params.reference_price)
+    } else if sell.symbol == params.pair.base && buy.symbol == params.pair.quote {
+        (cost * params.reference_price, buy.amount)
+    } else {
+        return Err(format!(
+            "Fill of {} for {} is not on the pair {}/{}",
+            sell.symbol, buy.symbol, params.pair.base, params.pair.quote,
+        ));
+    };
+    let expected_profit = value_received - cost_in_quote;
+    let required_profit = value_received * params.spread;
+    if expected_profit < required_profit {
+        return Err(format!(
+            "Fill of {} {} for {} {} yields {} {}, below the required {} {}",
+            sell.amount, sell.symbol, buy.amount, buy.symbol, expected_profit, params.pair.quote, required_profit, params.pair.quote,
+        ));
+    }
+
+    let available = inventory.get(&user_order.trade.buy.resource).cloned().unwrap_or_default();
+    let instamint = if available >= cost {
+        None
+    } else {
+        let shortfall = cost - available;
+        let headroom = instamint_headroom
+            .ok_or_else(|| format!("Insufficient {}: need {}, have {}", sell.symbol, cost, available))?;
+        headroom.check(&sell.symbol, shortfall)?;
+        Some(TokenAmount {
+            symbol: sell.symbol.clone(),
+            amount: shortfall,
+        })
+    };
+
+    Ok(FillQuote {
+        sell,
+        buy,
+        settlement_fee_amount,
+        anthic_fee_amount,
+        instamint,
+        expected_profit,
+    })
+}
+
+impl FillQuote {
+    /// Adds the instructions for the fill, instaminting first if needed
+    pub fn apply(
+        self,
+        builder: AnthicSubintentManifestBuilder,
+        account: &AnthicAccount,
+        instamint_config: &InstamintConfig,
+    ) -> Result<AnthicSubintentManifestBuilder, String> {
+        let builder = match self.instamint {
+            Some(to_mint) => {
+                let local_id = account.instamint_customer_badge_local_id().cloned()
+                    .ok_or("Cannot instamint without a customer badge")?;
+                builder.instamint_into_account(instamint_config, account.address, local_id, to_mint)
+            }
+            None => builder,
+        };
+        Ok(builder.add_anthic_limit_order(account.address, self.sell, self.buy, self.settlement_fee_amount, self.anthic_fee_amount))
+    }
+}
+
+fn symbol(tokens: &TokenRegistry, resource: &ResourceAddress) -> Result<String, String> {
+    tokens.symbol(resource)
+        .map(|symbol| symbol.to_string())
+        .ok_or_else(|| format!("Unknown resource: {:?}", resource))
+}
+
+#[cfg(test)]
+mod tests {
+    use anthic_model::AnthicLevelFee;
+    use super::*;
+    use crate::test_support::*;
+    use crate::validate::anthic_validate_manifest;
+
+    fn config() -> AnthicConfig {
+        let mut config = config_with(&[("xUSDC", usdc()), ("xwBTC", btc())]);
+        config.settlement_fee_per_resource = HashMap::from([
+            ("xUSDC".to_string(), dec!("0.1")),
+            ("xwBTC".to_string(), dec!("0.00001")),
+        ]);
+        config.anthic_fee_per_level = vec![AnthicLevelFee { taker_fee: dec!("0.001"), maker_fee: dec!(0) }];
+        config
+    }
+
+    /// A user selling 95.85 xUSDC for 0.001 xwBTC
+    fn user_order() -> AnthicLimitOrderDefinition {
+        let manifest = AnthicSubintentManifestBuilder::new(config())
+            .add_anthic_limit_order_with_options(account(), amount("xUSDC", dec!("95.85")), amount("xwBTC", dec!("0.001")), dec!("0.1"), dec!("0.1"), &Default::default())
+            .unwrap()
+            .build();
+        anthic_validate_manifest(&manifest).unwrap()
+    }
+
+    fn params(reference_price: Decimal, spread: Decimal) -> QuoteParams {
+        QuoteParams {
+            pair: TokenPair {
+                base: "xwBTC".to_string(),
+                quote: "xUSDC".to_string(),
+            },
+            reference_price,
+            spread,
+            level: 0,
+        }
+    }
+
+    fn inventory(btc_amount: Decimal) -> HashMap<ResourceAddress, Decimal> {
+        HashMap::from([(btc(), btc_amount)])
+    }
+
+    /// An allowance of 1 xwBTC with `outstanding` already minted
+    fn headroom(outstanding: Decimal) -> InstamintHeadroom {
+        InstamintHeadroom {
+            allowance: dec!(1),
+            outstanding: HashMap::from([("xwBTC".to_string(), outstanding)]),
+            remaining_per_token: HashMap::from([("xwBTC".to_string(), dec!(1) - outstanding)]),
+        }
+    }
+
+    #[test]
+    fn fills_invert_the_user_order() {
+        let quote = quote_fill(&config(), &user_order(), &params(dec!(90000), dec!("0.05")), &inventory(dec!(1)), None).unwrap();
+
+        assert_eq!(quote.sell, TokenAmount { symbol: "xwBTC".to_string(), amount: dec!("0.001") });
+        assert_eq!(quote.buy, TokenAmount { symbol: "xUSDC".to_string(), amount: dec!("95.85") });
+        assert_eq!(quote.settlement_fee_amount, dec!("0.00001"));
+        assert_eq!(quote.anthic_fee_amount, dec!(0));
+        assert_eq!(quote.instamint, None);
+        // 95.85 received for 0.00101 xwBTC worth 90.9
+        assert_eq!(quote.expected_profit, dec!("4.95"));
+    }
+
+    #[test]
+    fn fills_below_the_spread_are_rejected() {
+        // A profit of 4.95 is above 5% of 95.85 but below 6%
+        assert!(quote_fill(&config(), &user_order(), &params(dec!(90000), dec!("0.05")), &inventory(dec!(1)), None).is_ok());
+        assert_eq!(
+            quote_fill(&config(), &user_order(), &params(dec!(90000), dec!("0.06")), &inventory(dec!(1)), None).unwrap_err(),
+            "Fill of 0.001 xwBTC for 95.85 xUSDC yields 4.95 xUSDC, below the required 5.751 xUSDC",
+        );
+        // Unprofitable at a higher reference price
+        assert!(quote_fill(&config(), &user_order(), &params(dec!(95000), dec!(0)), &inventory(dec!(1)), None).is_err());
+    }
+
+    #[test]
+    fn fills_off_the_pair_or_level_are_rejected() {
+        let mut off_pair = params(dec!(90000), dec!(0));
+        off_pair.pair.quote = "XRD".to_string();
+        assert!(quote_fill(&config(), &user_order(), &off_pair, &inventory(dec!(1)), None).is_err());
+
+        let mut unknown_level = params(dec!(90000), dec!(0));
+        unknown_level.level = 1;
+        assert_eq!(quote_fill(&config(), &user_order(), &unknown_level, &inventory(dec!(1)), None).unwrap_err(), "No anthic fee for level 1");
+    }
+
+    #[test]
+    fn shortfalls_are_instaminted_within_the_headroom() {
+        let params = params(dec!(90000), dec!(0));
+
+        // 0.00101 needed with 0.0005 held
+        let quote = quote_fill(&config(), &user_order(), &params, &inventory(dec!("0.0005")), Some(&headroom(dec!("0.99949")))).unwrap();
+        assert_eq!(quote.instamint, Some(TokenAmount { symbol: "xwBTC".to_string(), amount: dec!("0.00051") }));
+
+        let result = quote_fill(&config(), &user_order(), &params, &inventory(dec!("0.0005")), Some(&headroom(dec!("0.9995"))));
+        assert!(result.unwrap_err().contains("exceeds remaining allowance"));
+
+        let result = quote_fill(&config(), &user_order(), &params, &inventory(dec!("0.0005")), None);
+        assert_eq!(result.unwrap_err(), "Insufficient xwBTC: need 0.00101, have 0.0005");
+    }
+}
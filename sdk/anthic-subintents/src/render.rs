@@ -0,0 +1,261 @@
+use radix_common::prelude::*;
+use radix_engine_interface::prelude::*;
+use serde::{Deserialize, Serialize};
+use anthic_model::{AnthicConfig, TokenRegistry};
+use crate::validate::{AnthicLimitOrderDefinition, FeeSource};
+use crate::BuyConstraint;
+
+/// Renders validated orders for logs, admin UIs and user confirmations, using token symbols
+/// where known and bech32 addresses otherwise
+pub struct OrderRenderer {
+    tokens: TokenRegistry,
+    encoder: AddressBech32Encoder,
+}
+
+/// JSON friendly form of an [`AnthicLimitOrderDefinition`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenderedOrder {
+    pub account: String,
+    pub sell: RenderedAmount,
+    /// The least amount to receive, exact if it equals `buy_max_amount`
+    pub buy: RenderedAmount,
+    /// The maximum amount to receive, unset if unbounded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buy_max_amount: Option<String>,
+    pub fee: RenderedFee,
+    pub verify_parent_access_rule_sbor_hex: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenderedAmount {
+    pub resource: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    pub amount: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub non_fungible_ids: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenderedFee {
+    pub resource: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    pub anthic_amount: String,
+    pub settlement_amount: String,
+    pub total_amount: String,
+    pub source: FeeSource,
+}
+
+impl OrderRenderer {
+    pub fn new(config: &AnthicConfig, network: &NetworkDefinition) -> Self {
+        Self::with_tokens(TokenRegistry::from_symbol_to_resource(&config.symbol_to_resource), network)
+    }
+
+    pub fn with_tokens(tokens: TokenRegistry, network: &NetworkDefinition) -> Self {
+        Self {
+            tokens,
+            encoder: AddressBech32Encoder::new(network),
+        }
+    }
+
+    /// e.g. `Sell 95.85 xUSDC for ≥0.001 xwBTC, fees 0.1 xUSDC, account account_tdx_2_1...`.
+    /// Fees not withdrawn with the sell amount are marked as withdrawn separately or taken from the proceeds.
+    pub fn summary(&self, order: &AnthicLimitOrderDefinition) -> String {
+        let fee_total = order.fee.anthic_amount + order.fee.settlement_amount;
+        let buy_amount = match &order.trade.buy_constraint {
+            BuyConstraint::AtLeast => format!("≥{}", order.trade.buy.amount),
+            BuyConstraint::Exact => order.trade.buy.amount.to_string(),
+            BuyConstraint::AmountRange { max } => format!("{}-{}", order.trade.buy.amount, max),
+        };
+        let fee_source = match order.fee.source {
+            FeeSource::Sell => "",
+            FeeSource::Withdraw => " withdrawn separately",
+            FeeSource::Proceeds => " from the proceeds",
+        };
+        format!(
+            "Sell {} {} for {} {}, fees {} {}{}, account {}",
+            order.trade.sell.amount,
+            self.resource_name(&order.trade.sell.resource),
+            buy_amount,
+            self.resource_name(&order.trade.buy.resource),
+            fee_total,
+            self.resource_name(&order.fee.resource),
+            fee_source,
+            self.encode(order.meta.account.as_bytes()),
+        )
+    }
+
+    pub fn render(&self, order: &AnthicLimitOrderDefinition) -> RenderedOrder {
+        let verify_parent_access_rule = scrypto_encode(&order.meta.access_rule).unwrap();
+        RenderedOrder {
+            account: self.encode(order.meta.account.as_bytes()),
+            sell: self.amount(&order.trade.sell.resource, order.trade.sell.amount, order.trade.sell_non_fungible_ids.as_ref()),
+            buy: self.amount(&order.trade.buy.resource, order.trade.buy.amount, order.trade.buy_non_fungible_ids.as_ref()),
+            buy_max_amount: match &order.trade.buy_constraint {
+                BuyConstraint::AtLeast => None,
+                BuyConstraint::Exact => Some(order.trade.buy.amount.to_string()),
+                BuyConstraint::AmountRange { max } => Some(max.to_string()),
+            },
+            fee: RenderedFee {
+                resource: self.encode(order.fee.resource.as_bytes()),
+                symbol: self.tokens.symbol(&order.fee.resource).map(|symbol| symbol.to_string()),
+                anthic_amount: order.fee.anthic_amount.to_string(),
+                settlement_amount: order.fee.settlement_amount.to_string(),
+                total_amount: (order.fee.anthic_amount + order.fee.settlement_amount).to_string(),
+                source: order.fee.source,
+            },
+            verify_parent_access_rule_sbor_hex: hex::encode(verify_parent_access_rule),
+            client_order_id: order.meta.message.client_order_id().map(|id| id.to_string()),
+        }
+    }
+
+    pub fn to_json(&self, order: &AnthicLimitOrderDefinition) -> serde_json::Value {
+        serde_json::to_value(self.render(order)).unwrap()
+    }
+
+    fn amount(&self, resource: &ResourceAddress, amount: Decimal, ids: Option<&BTreeSet<NonFungibleLocalId>>) -> RenderedAmount {
+        RenderedAmount {
+            resource: self.encode(resource.as_bytes()),
+            symbol: self.tokens.symbol(resource).map(|symbol| symbol.to_string()),
+            amount: amount.to_string(),
+            non_fungible_ids: ids.map(|ids| ids.iter().map(|id| id.to_string()).collect()),
+        }
+    }
+
+    fn resource_name(&self, resource: &ResourceAddress) -> String {
+        self.tokens.symbol(resource)
+            .map(|symbol| symbol.to_string())
+            .unwrap_or_else(|| self.encode(resource.as_bytes()))
+    }
+
+    fn encode(&self, address: &[u8]) -> String {
+        self.encoder.encode(address).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anthic_model::AnthicConfig;
+    use serde_json::json;
+    use super::*;
+    use crate::messages::SubintentMessage;
+    use crate::test_support::*;
+    use crate::validate::anthic_validate_manifest;
+    use crate::{AnthicSubintentManifestBuilder, LimitOrderOptions};
+
+    fn config() -> AnthicConfig {
+        config_with(&[("xUSDC", usdc()), ("xwBTC", btc()), ("XRD", xrd())])
+    }
+
+    fn order(settlement_fee: Decimal, anthic_fee: Decimal, options: &LimitOrderOptions) -> AnthicLimitOrderDefinition {
+        let manifest = AnthicSubintentManifestBuilder::new(config())
+            .add_anthic_limit_order_with_options(account(), amount("xUSDC", dec!("95.85")), amount("xwBTC", dec!("0.001")), settlement_fee, anthic_fee, options)
+            .unwrap()
+            .build();
+        anthic_validate_manifest(&manifest).unwrap()
+    }
+
+    fn account_address() -> String {
+        AddressBech32Encoder::new(&NetworkDefinition::stokenet()).encode(account().as_bytes()).unwrap()
+    }
+
+    fn renderer() -> OrderRenderer {
+        OrderRenderer::new(&config(), &NetworkDefinition::stokenet())
+    }
+
+    #[test]
+    fn summary_of_a_taker_order() {
+        let order = order(dec!("0.05"), dec!("0.05"), &LimitOrderOptions::default());
+        assert_eq!(
+            renderer().summary(&order),
+            format!("Sell 95.85 xUSDC for ≥0.001 xwBTC, fees 0.1 xUSDC, account {}", account_address()),
+        );
+    }
+
+    #[test]
+    fn summary_shows_the_buy_constraint() {
+        let exact = order(dec!("0.05"), dec!("0.05"), &LimitOrderOptions {
+            buy_constraint: BuyConstraint::Exact,
+            ..Default::default()
+        });
+        assert!(renderer().summary(&exact).starts_with("Sell 95.85 xUSDC for 0.001 xwBTC,"));
+
+        let range = order(dec!("0.05"), dec!("0.05"), &LimitOrderOptions {
+            buy_constraint: BuyConstraint::AmountRange { max: dec!("0.002") },
+            ..Default::default()
+        });
+        assert!(renderer().summary(&range).starts_with("Sell 95.85 xUSDC for 0.001-0.002 xwBTC,"));
+    }
+
+    #[test]
+    fn summary_shows_where_fees_come_from() {
+        let withdrawn = order(dec!(1), dec!("0.5"), &LimitOrderOptions {
+            fee_symbol: Some("XRD".to_string()),
+            ..Default::default()
+        });
+        assert!(renderer().summary(&withdrawn).contains(", fees 1.5 XRD withdrawn separately, account "));
+
+        let from_proceeds = order(dec!("0.0000005"), dec!("0.0000005"), &LimitOrderOptions {
+            fee_symbol: Some("xwBTC".to_string()),
+            ..Default::default()
+        });
+        assert!(renderer().summary(&from_proceeds).contains(", fees 0.000001 xwBTC from the proceeds, account "));
+    }
+
+    #[test]
+    fn unknown_resources_are_shown_as_addresses() {
+        let order = order(dec!("0.05"), dec!("0.05"), &LimitOrderOptions::default());
+        let renderer = OrderRenderer::with_tokens(TokenRegistry::default(), &NetworkDefinition::stokenet());
+        let usdc_address = AddressBech32Encoder::new(&NetworkDefinition::stokenet()).encode(usdc().as_bytes()).unwrap();
+
+        assert!(renderer.summary(&order).starts_with(&format!("Sell 95.85 {} for", usdc_address)));
+        assert_eq!(renderer.render(&order).sell.symbol, None);
+    }
+
+    #[test]
+    fn json_of_a_taker_order() {
+        let mut order = order(dec!("0.05"), dec!("0.05"), &LimitOrderOptions::default());
+        order.meta.message = SubintentMessage::ClientOrderId("order-1".to_string());
+        let encoder = AddressBech32Encoder::new(&NetworkDefinition::stokenet());
+
+        assert_eq!(renderer().to_json(&order), json!({
+            "account": account_address(),
+            "sell": {
+                "resource": encoder.encode(usdc().as_bytes()).unwrap(),
+                "symbol": "xUSDC",
+                "amount": "95.85",
+            },
+            "buy": {
+                "resource": encoder.encode(btc().as_bytes()).unwrap(),
+                "symbol": "xwBTC",
+                "amount": "0.001",
+            },
+            "fee": {
+                "resource": encoder.encode(usdc().as_bytes()).unwrap(),
+                "symbol": "xUSDC",
+                "anthic_amount": "0.05",
+                "settlement_amount": "0.05",
+                "total_amount": "0.1",
+                "source": "sell",
+            },
+            "verify_parent_access_rule_sbor_hex": hex::encode(scrypto_encode(&AccessRule::AllowAll).unwrap()),
+            "client_order_id": "order-1",
+        }));
+    }
+
+    #[test]
+    fn json_round_trips_through_rendered_order() {
+        let order = order(dec!(1), dec!("0.5"), &LimitOrderOptions {
+            buy_constraint: BuyConstraint::AmountRange { max: dec!("0.002") },
+            fee_symbol: Some("XRD".to_string()),
+        });
+        let rendered: RenderedOrder = serde_json::from_value(renderer().to_json(&order)).unwrap();
+
+        assert_eq!(rendered, renderer().render(&order));
+        assert_eq!(rendered.buy_max_amount.as_deref(), Some("0.002"));
+        assert_eq!(rendered.fee.source, FeeSource::Withdraw);
+    }
+}
@@ -0,0 +1,226 @@
+use radix_common::prelude::*;
+use radix_transactions::prelude::*;
+use anthic_model::{Chain, InstamintRepaymentInfo, TokenIdentifierOnChain};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepaymentAmount {
+    /// Repay the entire outstanding loan
+    Full,
+    /// Repay part of the outstanding loan, must not exceed it
+    Partial(Decimal),
+}
+
+/// A resolved repayment of a single instamint loan
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstamintRepayment {
+    pub symbol: String,
+    pub resource: ResourceAddress,
+    pub payback_address: ComponentAddress,
+    pub amount: Decimal,
+}
+
+/// Builds a transaction manifest repaying instamint loans from an account
+pub struct InstamintRepaymentManifestBuilder {
+    decoder: AddressBech32Decoder,
+    repayment_info: InstamintRepaymentInfo,
+    outstanding_loans: HashMap<String, Decimal>,
+    lock_fee: Option<Decimal>,
+    repayments: Vec<(String, RepaymentAmount)>,
+}
+
+impl InstamintRepaymentManifestBuilder {
+    /// `outstanding_loans` is as returned by `AnthicClient::get_instamint_balance`
+    pub fn new(
+        network: &NetworkDefinition,
+        repayment_info: InstamintRepaymentInfo,
+        outstanding_loans: HashMap<String, Decimal>,
+    ) -> Self {
+        Self {
+            decoder: AddressBech32Decoder::new(network),
+            repayment_info,
+            outstanding_loans,
+            lock_fee: None,
+            repayments: Vec::new(),
+        }
+    }
+
+    /// Locks the transaction fee from the repaying account
+    pub fn lock_fee(mut self, amount: Decimal) -> Self {
+        self.lock_fee = Some(amount);
+        self
+    }
+
+    pub fn repay(mut self, symbol: &str, amount: RepaymentAmount) -> Self {
+        self.repayments.push((symbol.to_string(), amount));
+        self
+    }
+
+    /// Resolves the resource, payback address and amount of a repayment.
+    ///
+    /// A payback address for the native token of Radix is repaid in XRD, whose resource address
+    /// is the same on every network.
+    pub fn resolve(&self, symbol: &str, amount: RepaymentAmount) -> Result<InstamintRepayment, String> {
+        let outstanding = self.outstanding_loans.get(symbol).cloned().unwrap_or_default();
+        let amount = match amount {
+            RepaymentAmount::Full => outstanding,
+            RepaymentAmount::Partial(amount) => {
+                if amount > outstanding {
+                    return Err(format!("Repayment of {} {} exceeds outstanding loan of {}", amount, symbol, outstanding));
+                }
+                amount
+            }
+        };
+        if !amount.is_positive() {
+            return Err(format!("Nothing to repay for {}", symbol));
+        }
+
+        let payback_address = self.repayment_info.get_repayment_address(symbol, &Chain::Radix)
+            .ok_or_else(|| format!("No Radix payback address for {}", symbol))?;
+        let resource = match &payback_address.token_identifier {
+            TokenIdentifierOnChain::Native => XRD,
+            TokenIdentifierOnChain::Address(address) => ResourceAddress::try_from_bech32(&self.decoder, address)
+                .ok_or_else(|| format!("Invalid repayment resource for {}: {}", symbol, address))?,
+        };
+        let address = ComponentAddress::try_from_bech32(&self.decoder, &payback_address.address)
+            .ok_or_else(|| format!("Invalid payback address for {}: {}", symbol, payback_address.address))?;
+
+        Ok(InstamintRepayment {
+            symbol: symbol.to_string(),
+            resource,
+            payback_address: address,
+            amount,
+        })
+    }
+
+    /// Builds the manifest, returning it together with the resolved repayments
+    pub fn build(self, account: ComponentAddress) -> Result<(TransactionManifestV1, Vec<InstamintRepayment>), String> {
+        if self.repayments.is_empty() {
+            return Err("No repayments".to_string());
+        }
+        let repayments = self.repayments.iter()
+            .map(|(symbol, amount)| self.resolve(symbol, *amount))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Each repayment is checked alone when resolved, so check the total of repeated symbols too
+        let mut totals: IndexMap<&str, Decimal> = IndexMap::default();
+        for repayment in &repayments {
+            *totals.entry(repayment.symbol.as_str()).or_default() += repayment.amount;
+        }
+        for (symbol, total) in totals {
+            let outstanding = self.outstanding_loans.get(symbol).cloned().unwrap_or_default();
+            if total > outstanding {
+                return Err(format!("Repayments of {} {} exceed outstanding loan of {}", total, symbol, outstanding));
+            }
+        }
+
+        let mut builder = ManifestBuilder::new();
+        if let Some(fee) = self.lock_fee {
+            builder = builder.lock_fee(account, fee);
+        }
+        for (index, repayment) in repayments.iter().enumerate() {
+            let bucket = format!("repayment-{}", index);
+            builder = builder
+                .withdraw_from_account(account, repayment.resource, repayment.amount)
+                .take_from_worktop(repayment.resource, repayment.amount, &bucket)
+                .try_deposit_or_abort(repayment.payback_address, None, &bucket);
+        }
+
+        Ok((builder.build(), repayments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anthic_model::InstamintTokenPaybackAddress;
+    use super::*;
+
+    const XUSDC: &str = "resource_tdx_2_1t5pqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszye50ea";
+    const PAYBACK_ACCOUNT: &str = "account_tdx_2_1cypqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszfpyz4c";
+
+    fn payback_address(token_identifier: TokenIdentifierOnChain) -> InstamintTokenPaybackAddress {
+        InstamintTokenPaybackAddress {
+            chain: Chain::Radix,
+            testnet: true,
+            symbol: "USDC".to_string(),
+            token_identifier,
+            address: PAYBACK_ACCOUNT.to_string(),
+        }
+    }
+
+    fn builder() -> InstamintRepaymentManifestBuilder {
+        let repayment_info = InstamintRepaymentInfo {
+            info: HashMap::from([
+                ("xUSDC".to_string(), vec![payback_address(TokenIdentifierOnChain::Address(XUSDC.to_string()))]),
+                ("XRD".to_string(), vec![payback_address(TokenIdentifierOnChain::Native)]),
+            ]),
+            invalid_addresses: Vec::new(),
+        };
+        let outstanding_loans = HashMap::from([("xUSDC".to_string(), dec!(100)), ("XRD".to_string(), dec!(5))]);
+        InstamintRepaymentManifestBuilder::new(&NetworkDefinition::stokenet(), repayment_info, outstanding_loans)
+    }
+
+    fn account() -> ComponentAddress {
+        ComponentAddress::try_from_bech32(&AddressBech32Decoder::new(&NetworkDefinition::stokenet()), PAYBACK_ACCOUNT).unwrap()
+    }
+
+    #[test]
+    fn repayments_are_resolved() {
+        let decoder = AddressBech32Decoder::new(&NetworkDefinition::stokenet());
+        let builder = builder();
+
+        let full = builder.resolve("xUSDC", RepaymentAmount::Full).unwrap();
+        assert_eq!(full, InstamintRepayment {
+            symbol: "xUSDC".to_string(),
+            resource: ResourceAddress::try_from_bech32(&decoder, XUSDC).unwrap(),
+            payback_address: account(),
+            amount: dec!(100),
+        });
+        assert_eq!(builder.resolve("xUSDC", RepaymentAmount::Partial(dec!(40))).unwrap().amount, dec!(40));
+        // The native token of Radix is XRD
+        assert_eq!(builder.resolve("XRD", RepaymentAmount::Full).unwrap().resource, XRD);
+    }
+
+    #[test]
+    fn invalid_repayments_are_rejected() {
+        let builder = builder();
+        assert!(builder.resolve("xUSDC", RepaymentAmount::Partial(dec!("100.1"))).is_err());
+        assert!(builder.resolve("xUSDC", RepaymentAmount::Partial(dec!(0))).is_err());
+        assert!(builder.resolve("xwBTC", RepaymentAmount::Full).is_err());
+        assert!(builder.build(account()).is_err());
+    }
+
+    #[test]
+    fn partial_repayments_are_checked_in_total() {
+        let within = builder()
+            .repay("xUSDC", RepaymentAmount::Partial(dec!(60)))
+            .repay("xUSDC", RepaymentAmount::Partial(dec!(40)))
+            .build(account());
+        assert_eq!(within.unwrap().1.len(), 2);
+
+        let exceeding = builder()
+            .repay("xUSDC", RepaymentAmount::Partial(dec!(60)))
+            .repay("xUSDC", RepaymentAmount::Partial(dec!(41)))
+            .build(account());
+        assert_eq!(exceeding.unwrap_err(), "Repayments of 101 xUSDC exceed outstanding loan of 100");
+
+        let repaid_twice = builder()
+            .repay("XRD", RepaymentAmount::Full)
+            .repay("XRD", RepaymentAmount::Partial(dec!(1)))
+            .build(account());
+        assert!(repaid_twice.is_err());
+    }
+
+    #[test]
+    fn manifest_withdraws_and_deposits_each_repayment() {
+        let (manifest, repayments) = builder()
+            .lock_fee(dec!(10))
+            .repay("xUSDC", RepaymentAmount::Full)
+            .repay("XRD", RepaymentAmount::Partial(dec!(2)))
+            .build(account())
+            .unwrap();
+
+        assert_eq!(repayments.iter().map(|repayment| repayment.amount).collect::<Vec<_>>(), vec![dec!(100), dec!(2)]);
+        // A fee lock, then a withdraw, take and deposit per repayment
+        assert_eq!(manifest.instructions.len(), 7);
+    }
+}
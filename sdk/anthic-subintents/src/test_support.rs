@@ -0,0 +1,76 @@
+//! Addresses, configs and order legs shared by the unit tests of this crate
+
+use radix_common::prelude::*;
+use radix_engine_interface::prelude::*;
+use anthic_model::{AnthicConfig, InstamintConfig};
+use crate::{OrderLeg, TokenAmount};
+
+pub fn resource(entity_type: EntityType, n: u8) -> ResourceAddress {
+    let mut bytes = [n; NodeId::LENGTH];
+    bytes[0] = entity_type as u8;
+    ResourceAddress::new_or_panic(bytes)
+}
+
+pub fn xrd() -> ResourceAddress {
+    resource(EntityType::GlobalFungibleResourceManager, 1)
+}
+
+pub fn usdc() -> ResourceAddress {
+    resource(EntityType::GlobalFungibleResourceManager, 2)
+}
+
+pub fn nft() -> ResourceAddress {
+    resource(EntityType::GlobalNonFungibleResourceManager, 3)
+}
+
+pub fn btc() -> ResourceAddress {
+    resource(EntityType::GlobalFungibleResourceManager, 5)
+}
+
+pub fn account() -> ComponentAddress {
+    let mut bytes = [4; NodeId::LENGTH];
+    bytes[0] = EntityType::GlobalAccount as u8;
+    ComponentAddress::new_or_panic(bytes)
+}
+
+pub fn instamint_config() -> InstamintConfig {
+    let mut component = [7; NodeId::LENGTH];
+    component[0] = EntityType::GlobalGenericComponent as u8;
+    InstamintConfig {
+        customer_badge_resource: resource(EntityType::GlobalNonFungibleResourceManager, 6),
+        instamint_component: ComponentAddress::new_or_panic(component),
+    }
+}
+
+/// A config mapping the given symbols, with fees left to each test
+pub fn config_with(symbols: &[(&str, ResourceAddress)]) -> AnthicConfig {
+    AnthicConfig {
+        verify_parent_access_rule: AccessRule::AllowAll,
+        symbol_to_resource: symbols.iter().map(|(symbol, resource)| (symbol.to_string(), *resource)).collect(),
+        settlement_fee_per_resource: Default::default(),
+        anthic_fee_per_level: Vec::new(),
+    }
+}
+
+/// XRD, USDC, NFT and BTC
+pub fn config() -> AnthicConfig {
+    config_with(&[("XRD", xrd()), ("USDC", usdc()), ("NFT", nft()), ("BTC", btc())])
+}
+
+pub fn amount(symbol: &str, amount: Decimal) -> OrderLeg {
+    OrderLeg::Amount(TokenAmount {
+        symbol: symbol.to_string(),
+        amount,
+    })
+}
+
+pub fn nfts(ids: &[u64]) -> OrderLeg {
+    OrderLeg::NonFungibles {
+        symbol: "NFT".to_string(),
+        ids: ids.iter().map(|id| NonFungibleLocalId::integer(*id)).collect(),
+    }
+}
+
+pub fn ids(ids: &[u64]) -> Option<BTreeSet<NonFungibleLocalId>> {
+    Some(ids.iter().map(|id| NonFungibleLocalId::integer(*id)).collect())
+}
@@ -1,17 +1,26 @@
 use radix_common::math::Decimal;
 use radix_common::prelude::*;
-use radix_engine_interface::blueprints::account::{AccountWithdrawManifestInput, ACCOUNT_DEPOSIT_BATCH_IDENT, ACCOUNT_WITHDRAW_IDENT};
+use radix_engine_interface::blueprints::account::{
+    AccountWithdrawManifestInput, AccountWithdrawNonFungiblesManifestInput, ACCOUNT_DEPOSIT_BATCH_IDENT, ACCOUNT_WITHDRAW_IDENT,
+    ACCOUNT_WITHDRAW_NON_FUNGIBLES_IDENT,
+};
 use radix_engine_interface::prelude::*;
 use radix_transactions::manifest::*;
 use radix_transactions::model::InstructionV2;
 use radix_transactions::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::messages::{extract_message, SubintentMessage};
+use crate::BuyConstraint;
 
 pub fn anthic_validate_manifest(manifest: &SubintentManifestV2) -> Result<AnthicLimitOrderDefinition, String> {
     anthic_validate_instructions(&manifest.instructions)
 }
 
+/// Validates the instructions and extracts the message of the subintent
 pub fn anthic_validate_subintent(subintent: &SubintentV2) -> Result<AnthicLimitOrderDefinition, String> {
-    anthic_validate_instructions(&subintent.intent_core.instructions.0)
+    let mut order = anthic_validate_instructions(&subintent.intent_core.instructions.0)?;
+    order.meta.message = extract_message(&subintent.intent_core.message);
+    Ok(order)
 }
 
 pub fn anthic_validate_instructions(instructions: &Vec<InstructionV2>) -> Result<AnthicLimitOrderDefinition, String> {
@@ -43,18 +52,41 @@ pub struct FeeDefinition {
     pub resource: ResourceAddress,
     pub anthic_amount: Decimal,
     pub settlement_amount: Decimal,
+    pub source: FeeSource,
+}
+
+/// Where the fees of an order come from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeSource {
+    /// Withdrawn together with the sell amount
+    Sell,
+    /// Withdrawn from the account separately, in a third resource
+    Withdraw,
+    /// Taken from the buy proceeds
+    Proceeds,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub struct LimitOrderMeta {
     pub access_rule: AccessRule,
     pub account: ComponentAddress,
+    /// Only set when validating a whole subintent
+    pub message: SubintentMessage,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub struct LimitOrder {
+    /// For non-fungible resources the amount is a count of non-fungibles
     pub sell: ResourceAmount,
+    /// The amount to receive, which `buy_constraint` makes a minimum, an exact amount or the
+    /// lower bound of a range
     pub buy: ResourceAmount,
+    pub buy_constraint: BuyConstraint,
+    /// The exact non-fungibles sold, if the order names them
+    pub sell_non_fungible_ids: Option<BTreeSet<NonFungibleLocalId>>,
+    /// The exact non-fungibles bought, if the order names them
+    pub buy_non_fungible_ids: Option<BTreeSet<NonFungibleLocalId>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Ord, PartialOrd)]
@@ -115,11 +147,38 @@ impl SubintentValidator {
                                     meta: LimitOrderMeta {
                                         access_rule: access_rule.clone(),
                                         account: address.clone().try_into().unwrap(),
+                                        message: SubintentMessage::None,
                                     },
                                     withdraw: ResourceAmount {
                                         resource,
                                         amount: withdraw.amount,
                                     },
+                                    withdraw_ids: None,
+                                };
+                            return Ok(());
+                        }
+                    }
+
+                    if address.as_node_id().is_global_account()
+                        && method_name.eq(ACCOUNT_WITHDRAW_NON_FUNGIBLES_IDENT)
+                    {
+                        let withdraw: AccountWithdrawNonFungiblesManifestInput =
+                            manifest_decode(&manifest_encode(&args).unwrap())
+                                .map_err(|err| format!("Decode error: {:?}", err))?;
+                        if let ManifestResourceAddress::Static(resource) = withdraw.resource_address {
+                            let ids: BTreeSet<NonFungibleLocalId> = withdraw.ids.into_iter().collect();
+                            state.qualified_state =
+                                SubintentValidatorQualifiedState::WithdrewFromAccount {
+                                    meta: LimitOrderMeta {
+                                        access_rule: access_rule.clone(),
+                                        account: address.clone().try_into().unwrap(),
+                                        message: SubintentMessage::None,
+                                    },
+                                    withdraw: ResourceAmount {
+                                        resource,
+                                        amount: Decimal::from(ids.len()),
+                                    },
+                                    withdraw_ids: Some(ids),
                                 };
                             return Ok(());
                         }
@@ -132,24 +191,48 @@ impl SubintentValidator {
                 ))
             }
 
-            SubintentValidatorQualifiedState::WithdrewFromAccount { meta, withdraw } => {
-                if let InstructionV2::TakeFromWorktop(TakeFromWorktop {
-                                                          resource_address,
-                                                          amount,
-                                                      }) = &instruction
-                {
-                    if withdraw.amount >= *amount && withdraw.resource.eq(resource_address) {
-                        state.qualified_state = SubintentValidatorQualifiedState::CreatedSellBucket {
-                            meta: meta.clone(),
-                            sell: ResourceAmount {
-                                resource: *resource_address,
-                                amount: *amount,
-                            },
-                            sell_bucket: ManifestBucket(cur_bucket),
-                            leftover: withdraw.amount - *amount,
-                        };
-                        return Ok(());
+            SubintentValidatorQualifiedState::WithdrewFromAccount { meta, withdraw, withdraw_ids } => {
+                match &instruction {
+                    InstructionV2::TakeFromWorktop(TakeFromWorktop {
+                                                       resource_address,
+                                                       amount,
+                                                   }) => {
+                        if withdraw.amount >= *amount && withdraw.resource.eq(resource_address) {
+                            state.qualified_state = SubintentValidatorQualifiedState::CreatedSellBucket {
+                                meta: meta.clone(),
+                                sell: ResourceAmount {
+                                    resource: *resource_address,
+                                    amount: *amount,
+                                },
+                                sell_ids: None,
+                                sell_bucket: ManifestBucket(cur_bucket),
+                                leftover: withdraw.amount - *amount,
+                            };
+                            return Ok(());
+                        }
                     }
+                    InstructionV2::TakeNonFungiblesFromWorktop(TakeNonFungiblesFromWorktop {
+                                                                   resource_address,
+                                                                   ids,
+                                                               }) => {
+                        let ids: BTreeSet<NonFungibleLocalId> = ids.iter().cloned().collect();
+                        if let Some(withdraw_ids) = withdraw_ids {
+                            if !ids.is_empty() && ids.is_subset(withdraw_ids) && withdraw.resource.eq(resource_address) {
+                                state.qualified_state = SubintentValidatorQualifiedState::CreatedSellBucket {
+                                    meta: meta.clone(),
+                                    sell: ResourceAmount {
+                                        resource: *resource_address,
+                                        amount: Decimal::from(ids.len()),
+                                    },
+                                    leftover: withdraw.amount - Decimal::from(ids.len()),
+                                    sell_ids: Some(ids),
+                                    sell_bucket: ManifestBucket(cur_bucket),
+                                };
+                                return Ok(());
+                            }
+                        }
+                    }
+                    _ => {}
                 }
 
                 Err(format!(
@@ -160,6 +243,7 @@ impl SubintentValidator {
             SubintentValidatorQualifiedState::CreatedSellBucket {
                 meta,
                 sell,
+                sell_ids,
                 sell_bucket,
                 leftover,
             } => {
@@ -169,23 +253,40 @@ impl SubintentValidator {
                                                              }) => {
                         if constraints.len() == 1 {
                             let (resource, constraint) = constraints.iter().next().unwrap();
-                            match constraint {
-                                ManifestResourceConstraint::AtLeastAmount(amount) => {
-                                    state.qualified_state =
-                                        SubintentValidatorQualifiedState::AssertedNextCallReturns {
-                                            meta: meta.clone(),
-                                            trade: LimitOrder {
-                                                sell: sell.clone(),
-                                                buy: ResourceAmount {
-                                                    resource: *resource,
-                                                    amount: *amount,
-                                                },
-                                            },
-                                            sell_bucket: sell_bucket.clone(),
-                                        };
-                                    return Ok(());
+                            let buy = match constraint {
+                                ManifestResourceConstraint::AtLeastAmount(amount) => Some((*amount, BuyConstraint::AtLeast, None)),
+                                ManifestResourceConstraint::ExactAmount(amount) => Some((*amount, BuyConstraint::Exact, None)),
+                                ManifestResourceConstraint::ExactNonFungibles(ids) if !ids.is_empty() => {
+                                    Some((Decimal::from(ids.len()), BuyConstraint::Exact, Some(ids.iter().cloned().collect())))
+                                }
+                                ManifestResourceConstraint::General(GeneralResourceConstraint {
+                                                                        required_ids,
+                                                                        lower_bound: LowerBound::Inclusive(min),
+                                                                        upper_bound: UpperBound::Inclusive(max),
+                                                                        allowed_ids: AllowedIds::Any,
+                                                                    }) if required_ids.is_empty() && min <= max => {
+                                    Some((*min, BuyConstraint::AmountRange { max: *max }, None))
                                 }
-                                _ => {}
+                                _ => None,
+                            };
+                            if let Some((amount, buy_constraint, buy_ids)) = buy {
+                                state.qualified_state =
+                                    SubintentValidatorQualifiedState::AssertedNextCallReturns {
+                                        meta: meta.clone(),
+                                        trade: LimitOrder {
+                                            sell: sell.clone(),
+                                            buy: ResourceAmount {
+                                                resource: *resource,
+                                                amount,
+                                            },
+                                            buy_constraint,
+                                            sell_non_fungible_ids: sell_ids.clone(),
+                                            buy_non_fungible_ids: buy_ids,
+                                        },
+                                        sell_bucket: sell_bucket.clone(),
+                                        leftover: *leftover,
+                                    };
+                                return Ok(());
                             }
                         }
                     }
@@ -202,6 +303,7 @@ impl SubintentValidator {
                 meta,
                 trade,
                 sell_bucket,
+                leftover,
             } => {
                 if let InstructionV2::YieldToParent(YieldToParent { args }) = &instruction {
                     if let Some(passed_to_parent) = try_manifest_args_to_bucket(args) {
@@ -210,6 +312,8 @@ impl SubintentValidator {
                                 SubintentValidatorQualifiedState::YieldedSellBucketToParent {
                                     meta: meta.clone(),
                                     trade: trade.clone(),
+                                    leftover: *leftover,
+                                    fee_withdraw: None,
                                 };
                             return Ok(());
                         }
@@ -222,26 +326,61 @@ impl SubintentValidator {
                 ))
             }
 
-            SubintentValidatorQualifiedState::YieldedSellBucketToParent { meta, trade } => {
-                if let InstructionV2::TakeFromWorktop(TakeFromWorktop {
-                                                          resource_address,
-                                                          amount,
-                                                      }) = &instruction
-                {
-                    state.qualified_state = SubintentValidatorQualifiedState::CreatedAnthicFeeBucket {
-                        meta: meta.clone(),
-                        trade: trade.clone(),
-                        anthic_fee: ResourceAmount {
-                            resource: *resource_address,
-                            amount: *amount,
-                        },
-                        anthic_fee_bucket: ManifestBucket(cur_bucket),
-                    };
-                    return Ok(());
+            SubintentValidatorQualifiedState::YieldedSellBucketToParent { meta, trade, leftover, fee_withdraw } => {
+                match &instruction {
+                    InstructionV2::CallMethod(CallMethod {
+                                                  address: DynamicGlobalAddress::Static(address),
+                                                  method_name,
+                                                  args,
+                                              }) => {
+                        if fee_withdraw.is_none()
+                            && address.as_node_id().eq(meta.account.as_node_id())
+                            && method_name.eq(ACCOUNT_WITHDRAW_IDENT)
+                        {
+                            let withdraw: AccountWithdrawManifestInput =
+                                manifest_decode(&manifest_encode(&args).unwrap())
+                                    .map_err(|err| format!("Decode error: {:?}", err))?;
+                            if let ManifestResourceAddress::Static(resource) = withdraw.resource_address {
+                                if resource.ne(&trade.sell.resource) && resource.ne(&trade.buy.resource) {
+                                    state.qualified_state =
+                                        SubintentValidatorQualifiedState::YieldedSellBucketToParent {
+                                            meta: meta.clone(),
+                                            trade: trade.clone(),
+                                            leftover: *leftover,
+                                            fee_withdraw: Some(ResourceAmount {
+                                                resource,
+                                                amount: withdraw.amount,
+                                            }),
+                                        };
+                                    return Ok(());
+                                }
+                            }
+                        }
+                    }
+                    InstructionV2::TakeFromWorktop(TakeFromWorktop {
+                                                       resource_address,
+                                                       amount,
+                                                   }) => {
+                        if fee_source(trade, fee_withdraw, resource_address).is_some() {
+                            state.qualified_state = SubintentValidatorQualifiedState::CreatedAnthicFeeBucket {
+                                meta: meta.clone(),
+                                trade: trade.clone(),
+                                leftover: *leftover,
+                                fee_withdraw: fee_withdraw.clone(),
+                                anthic_fee: ResourceAmount {
+                                    resource: *resource_address,
+                                    amount: *amount,
+                                },
+                                anthic_fee_bucket: ManifestBucket(cur_bucket),
+                            };
+                            return Ok(());
+                        }
+                    }
+                    _ => {}
                 }
 
                 Err(format!(
-                    "Expected Take From Worktop method but was: {:?}",
+                    "Expected Take From Worktop method of a withdrawn or bought resource but was: {:?}",
                     instruction
                 ))
             }
@@ -249,6 +388,8 @@ impl SubintentValidator {
             SubintentValidatorQualifiedState::CreatedAnthicFeeBucket {
                 meta,
                 trade,
+                leftover,
+                fee_withdraw,
                 anthic_fee,
                 anthic_fee_bucket,
             } => {
@@ -257,7 +398,16 @@ impl SubintentValidator {
                                                           amount,
                                                       }) = &instruction
                 {
-                    if resource_address.eq(&anthic_fee.resource) {
+                    let fees = anthic_fee.amount + *amount;
+                    // Fees must come out of what was withdrawn for them, proceeds are checked by the assertion
+                    let source = fee_source(trade, fee_withdraw, resource_address)
+                        .filter(|source| match (source, fee_withdraw) {
+                            (FeeSource::Withdraw, Some(withdraw)) => withdraw.amount >= fees,
+                            (FeeSource::Sell, _) => *leftover >= fees,
+                            _ => true,
+                        })
+                        .filter(|_| resource_address.eq(&anthic_fee.resource));
+                    if let Some(source) = source {
                         state.qualified_state =
                             SubintentValidatorQualifiedState::CreatedSettlementFeeBucket {
                                 order: AnthicLimitOrderDefinition {
@@ -267,6 +417,7 @@ impl SubintentValidator {
                                         resource: *resource_address,
                                         anthic_amount: anthic_fee.amount,
                                         settlement_amount: *amount,
+                                        source,
                                     },
                                 },
                                 anthic_fee_bucket: anthic_fee_bucket.clone(),
@@ -388,10 +539,12 @@ enum SubintentValidatorQualifiedState {
     WithdrewFromAccount {
         meta: LimitOrderMeta,
         withdraw: ResourceAmount,
+        withdraw_ids: Option<BTreeSet<NonFungibleLocalId>>,
     },
     CreatedSellBucket {
         meta: LimitOrderMeta,
         sell: ResourceAmount,
+        sell_ids: Option<BTreeSet<NonFungibleLocalId>>,
         sell_bucket: ManifestBucket,
         leftover: Decimal,
     },
@@ -399,14 +552,20 @@ enum SubintentValidatorQualifiedState {
         meta: LimitOrderMeta,
         trade: LimitOrder,
         sell_bucket: ManifestBucket,
+        leftover: Decimal,
     },
     YieldedSellBucketToParent {
         meta: LimitOrderMeta,
         trade: LimitOrder,
+        /// Withdrawn with the sell amount, to pay fees in the sell resource
+        leftover: Decimal,
+        fee_withdraw: Option<ResourceAmount>,
     },
     CreatedAnthicFeeBucket {
         meta: LimitOrderMeta,
         trade: LimitOrder,
+        leftover: Decimal,
+        fee_withdraw: Option<ResourceAmount>,
         anthic_fee: ResourceAmount,
         anthic_fee_bucket: ManifestBucket,
     },
@@ -442,6 +601,20 @@ impl SubintentValidatorState {
     }
 }
 
+/// Fees are only paid in fungible resources, whichever their source
+fn fee_source(trade: &LimitOrder, fee_withdraw: &Option<ResourceAmount>, resource: &ResourceAddress) -> Option<FeeSource> {
+    if !resource.is_fungible() {
+        return None;
+    }
+    match fee_withdraw {
+        Some(withdraw) if withdraw.resource.eq(resource) => Some(FeeSource::Withdraw),
+        Some(_) => None,
+        None if trade.sell.resource.eq(resource) => Some(FeeSource::Sell),
+        None if trade.buy.resource.eq(resource) => Some(FeeSource::Proceeds),
+        None => None,
+    }
+}
+
 fn try_manifest_args_to_bucket(args: &ManifestValue) -> Option<&ManifestBucket> {
     match args {
         ManifestValue::Tuple { fields } => {
@@ -490,4 +663,200 @@ fn try_manifest_args_to_two_buckets(
     }
 
     None
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use crate::{AnthicSubintentManifestBuilder, LimitOrderOptions, OrderLeg};
+
+    /// Builds an order with a settlement fee of 1 and an anthic fee of 0.5, and validates it
+    fn round_trip(sell: OrderLeg, buy: OrderLeg, options: &LimitOrderOptions) -> Result<AnthicLimitOrderDefinition, String> {
+        let manifest = AnthicSubintentManifestBuilder::new(config())
+            .add_anthic_limit_order_with_options(account(), sell, buy, dec!(1), dec!("0.5"), options)?
+            .build();
+        anthic_validate_manifest(&manifest)
+    }
+
+    #[test]
+    fn fungible_legs_round_trip() {
+        let order = round_trip(amount("XRD", dec!(100)), amount("USDC", dec!(10)), &LimitOrderOptions::default()).unwrap();
+        assert_eq!(order.meta.account, account());
+        assert_eq!(order.trade.sell, ResourceAmount { resource: xrd(), amount: dec!(100) });
+        assert_eq!(order.trade.buy, ResourceAmount { resource: usdc(), amount: dec!(10) });
+        assert_eq!(order.trade.sell_non_fungible_ids, None);
+        assert_eq!(order.trade.buy_non_fungible_ids, None);
+    }
+
+    #[test]
+    fn non_fungible_sell_round_trips() {
+        let order = round_trip(nfts(&[1, 2]), amount("USDC", dec!(10)), &LimitOrderOptions::default()).unwrap();
+        assert_eq!(order.trade.sell, ResourceAmount { resource: nft(), amount: dec!(2) });
+        assert_eq!(order.trade.sell_non_fungible_ids, ids(&[1, 2]));
+        // The sell resource cannot pay fees, so they come from the proceeds
+        assert_eq!(order.fee.resource, usdc());
+        assert_eq!(order.fee.source, FeeSource::Proceeds);
+    }
+
+    #[test]
+    fn non_fungible_buy_round_trips() {
+        let order = round_trip(amount("XRD", dec!(100)), nfts(&[7]), &LimitOrderOptions::default()).unwrap();
+        assert_eq!(order.trade.buy, ResourceAmount { resource: nft(), amount: dec!(1) });
+        assert_eq!(order.trade.buy_non_fungible_ids, ids(&[7]));
+        assert_eq!(order.trade.buy_constraint, BuyConstraint::Exact);
+        assert_eq!(order.fee.source, FeeSource::Sell);
+    }
+
+    #[test]
+    fn non_fungible_count_round_trips() {
+        let order = round_trip(amount("XRD", dec!(100)), amount("NFT", dec!(2)), &LimitOrderOptions::default()).unwrap();
+        assert_eq!(order.trade.buy, ResourceAmount { resource: nft(), amount: dec!(2) });
+        assert_eq!(order.trade.buy_non_fungible_ids, None);
+        assert_eq!(order.trade.buy_constraint, BuyConstraint::AtLeast);
+    }
+
+    #[test]
+    fn buy_constraints_round_trip_for_each_leg() {
+        let constraints = [BuyConstraint::AtLeast, BuyConstraint::Exact, BuyConstraint::AmountRange { max: dec!(12) }];
+        let sells = [amount("XRD", dec!(100)), nfts(&[1])];
+        let buys = [amount("USDC", dec!(10)), amount("NFT", dec!(2)), nfts(&[2, 3])];
+
+        for sell in &sells {
+            for buy in &buys {
+                for buy_constraint in &constraints {
+                    // Two non-fungible legs leave no resource to pay fees in by default
+                    let fee_symbol = (sell.symbol() == "NFT" && buy.symbol() == "NFT").then(|| "XRD".to_string());
+                    let options = LimitOrderOptions {
+                        buy_constraint: buy_constraint.clone(),
+                        fee_symbol,
+                    };
+                    let result = round_trip(sell.clone(), buy.clone(), &options);
+
+                    match (buy, buy_constraint) {
+                        (OrderLeg::NonFungibles { .. }, BuyConstraint::AmountRange { .. }) => assert!(result.is_err()),
+                        (OrderLeg::NonFungibles { ids: buy_ids, .. }, _) => {
+                            let order = result.unwrap();
+                            assert_eq!(order.trade.buy_constraint, BuyConstraint::Exact);
+                            assert_eq!(order.trade.buy_non_fungible_ids.as_ref(), Some(buy_ids));
+                            assert_eq!(order.trade.buy.amount, Decimal::from(buy_ids.len()));
+                        }
+                        (OrderLeg::Amount(token), _) => {
+                            let order = result.unwrap();
+                            assert_eq!(order.trade.buy_constraint, *buy_constraint);
+                            assert_eq!(order.trade.buy.amount, token.amount);
+                            assert_eq!(order.trade.buy_non_fungible_ids, None);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_buy_ranges_are_rejected() {
+        let options = LimitOrderOptions {
+            buy_constraint: BuyConstraint::AmountRange { max: dec!(5) },
+            ..Default::default()
+        };
+        assert!(round_trip(amount("XRD", dec!(100)), amount("USDC", dec!(10)), &options).is_err());
+    }
+
+    #[test]
+    fn non_fungible_legs_need_a_fungible_fee_resource() {
+        assert!(round_trip(nfts(&[1]), nfts(&[2]), &LimitOrderOptions::default()).is_err());
+
+        let nft_fees = LimitOrderOptions {
+            fee_symbol: Some("NFT".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            round_trip(amount("XRD", dec!(100)), amount("USDC", dec!(10)), &nft_fees),
+            Err("Fees must be paid in a fungible resource".to_string()),
+        );
+
+        let xrd_fees = LimitOrderOptions {
+            fee_symbol: Some("XRD".to_string()),
+            ..Default::default()
+        };
+        let order = round_trip(nfts(&[1]), nfts(&[2]), &xrd_fees).unwrap();
+        assert_eq!(order.fee.resource, xrd());
+        assert_eq!(order.fee.source, FeeSource::Withdraw);
+    }
+
+    #[test]
+    fn each_fee_source_round_trips() {
+        let cases = [
+            (None, xrd(), FeeSource::Sell),
+            (Some("USDC"), usdc(), FeeSource::Proceeds),
+            (Some("BTC"), btc(), FeeSource::Withdraw),
+        ];
+        for (fee_symbol, fee_resource, source) in cases {
+            let options = LimitOrderOptions {
+                fee_symbol: fee_symbol.map(str::to_string),
+                ..Default::default()
+            };
+            let order = round_trip(amount("XRD", dec!(100)), amount("USDC", dec!(10)), &options).unwrap();
+            assert_eq!(order.fee, FeeDefinition {
+                resource: fee_resource,
+                anthic_amount: dec!("0.5"),
+                settlement_amount: dec!(1),
+                source,
+            });
+            assert_eq!(order.trade.sell.amount, dec!(100));
+        }
+    }
+
+    /// An order selling 100 XRD with 1.5 XRD of fees, withdrawing `withdraw_amount`
+    fn sell_fee_order(withdraw_amount: Decimal) -> SubintentManifestV2 {
+        SubintentManifestV2Builder::new_subintent_v2()
+            .verify_parent(AccessRule::AllowAll)
+            .withdraw_from_account(account(), xrd(), withdraw_amount)
+            .take_from_worktop(xrd(), dec!(100), "sell")
+            .assert_next_call_returns_only(
+                ManifestResourceConstraints::new().with(usdc(), ManifestResourceConstraint::AtLeastAmount(dec!(10)))
+            )
+            .with_bucket("sell", |builder, bucket| builder.yield_to_parent((bucket,)))
+            .take_from_worktop(xrd(), dec!("0.5"), "anthic-fee")
+            .take_from_worktop(xrd(), dec!(1), "settlement-fee")
+            .with_name_lookup(|builder, lookup| {
+                builder.yield_to_parent((lookup.bucket("anthic-fee"), lookup.bucket("settlement-fee")))
+            })
+            .deposit_entire_worktop(account())
+            .yield_to_parent(())
+            .build()
+    }
+
+    /// An order selling 100 XRD for NFT #2, taking 1.5 of fees from the bought NFT resource
+    fn non_fungible_proceeds_fee_order() -> SubintentManifestV2 {
+        SubintentManifestV2Builder::new_subintent_v2()
+            .verify_parent(AccessRule::AllowAll)
+            .withdraw_from_account(account(), xrd(), dec!(100))
+            .take_from_worktop(xrd(), dec!(100), "sell")
+            .assert_next_call_returns_only(
+                ManifestResourceConstraints::new().with(nft(), ManifestResourceConstraint::ExactNonFungibles(
+                    ids(&[2]).unwrap().into_iter().collect(),
+                ))
+            )
+            .with_bucket("sell", |builder, bucket| builder.yield_to_parent((bucket,)))
+            .take_from_worktop(nft(), dec!("0.5"), "anthic-fee")
+            .take_from_worktop(nft(), dec!(1), "settlement-fee")
+            .with_name_lookup(|builder, lookup| {
+                builder.yield_to_parent((lookup.bucket("anthic-fee"), lookup.bucket("settlement-fee")))
+            })
+            .deposit_entire_worktop(account())
+            .yield_to_parent(())
+            .build()
+    }
+
+    #[test]
+    fn fees_from_non_fungible_proceeds_are_rejected() {
+        assert!(anthic_validate_manifest(&non_fungible_proceeds_fee_order()).is_err());
+    }
+
+    #[test]
+    fn sell_fees_must_be_covered_by_the_withdraw() {
+        assert!(anthic_validate_manifest(&sell_fee_order(dec!("101.5"))).is_ok());
+        assert!(anthic_validate_manifest(&sell_fee_order(dec!(101))).is_err());
+        assert!(anthic_validate_manifest(&sell_fee_order(dec!(100))).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use std::time::Duration;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use crate::error::TradeApiError;
+use crate::fixtures::API_KEY_HEADER;
+use crate::retry::{RateLimiter, RetryPolicy};
+use crate::transport::ReqwestTransport;
+use crate::AnthicTradeApiClient;
+
+/// Configures an [`AnthicTradeApiClient`] backed by reqwest. All settings are validated in [`Self::build`].
+#[derive(Default)]
+pub struct AnthicTradeApiClientBuilder {
+    url: Option<String>,
+    api_key: Option<String>,
+    client: Option<reqwest::Client>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    default_headers: Vec<(String, String)>,
+    proxy: Option<String>,
+    no_proxy: bool,
+    user_agent: Option<String>,
+    root_certificates: Vec<reqwest::Certificate>,
+    accept_invalid_certs: bool,
+    retry_policy: Option<RetryPolicy>,
+    rate_limit: Option<(f64, u32)>,
+}
+
+impl AnthicTradeApiClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Base url of the trade api, e.g. `https://trade-api.staging.anthic.io`
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Use a preconfigured reqwest client. This cannot be combined with the other connection settings.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Timeout for a whole request, from connecting until the response body has been read
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// A header sent with every request
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.default_headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Proxy all requests through the given url, e.g. `http://localhost:8080`
+    pub fn proxy(mut self, proxy_url: impl Into<String>) -> Self {
+        self.proxy = Some(proxy_url.into());
+        self
+    }
+
+    /// Ignore proxies configured through environment variables
+    pub fn no_proxy(mut self) -> Self {
+        self.no_proxy = true;
+        self
+    }
+
+    /// Identifies your application to Anthic, e.g. `my-market-maker/1.2.0`
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    pub fn add_root_certificate(mut self, certificate: reqwest::Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Disables TLS certificate validation. Only use this against local test servers.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Defaults to [`RetryPolicy::default`], which retries idempotent requests only
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Limit requests sent by this client to `requests_per_second` on average, with bursts of up to `burst`
+    pub fn rate_limit(mut self, requests_per_second: f64, burst: u32) -> Self {
+        self.rate_limit = Some((requests_per_second, burst));
+        self
+    }
+
+    pub fn build(mut self) -> Result<AnthicTradeApiClient, TradeApiError> {
+        let url = self.validated_url()?;
+        let api_key = self.api_key.clone().unwrap_or_default();
+        if !api_key.is_empty() {
+            HeaderValue::from_str(&api_key)
+                .map_err(|_| invalid_config(format!("{} is not a valid header value", API_KEY_HEADER)))?;
+        }
+
+        let retry_policy = self.retry_policy.take().unwrap_or_default();
+        retry_policy.validate()?;
+        let rate_limiter = self.rate_limit.take()
+            .map(|(requests_per_second, burst)| RateLimiter::new(requests_per_second, burst))
+            .transpose()?;
+
+        let client = match self.client.take() {
+            Some(client) => {
+                if self.has_connection_settings() {
+                    return Err(invalid_config("A custom reqwest client cannot be combined with other connection settings".to_string()));
+                }
+                client
+            }
+            None => self.build_reqwest_client()?,
+        };
+
+        let mut trade_api_client = AnthicTradeApiClient::with_transport(ReqwestTransport::with_client(client, url), api_key)
+            .with_retry_policy(retry_policy)?;
+        if let Some(rate_limiter) = rate_limiter {
+            trade_api_client = trade_api_client.with_rate_limiter(rate_limiter);
+        }
+        Ok(trade_api_client)
+    }
+
+    fn validated_url(&self) -> Result<String, TradeApiError> {
+        let url = self.url.as_deref().ok_or_else(|| invalid_config("Missing url".to_string()))?;
+        let parsed = reqwest::Url::parse(url).map_err(|err| invalid_config(format!("Invalid url {}: {}", url, err)))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(invalid_config(format!("Unsupported url scheme: {}", parsed.scheme())));
+        }
+        Ok(url.trim_end_matches('/').to_string())
+    }
+
+    fn has_connection_settings(&self) -> bool {
+        self.timeout.is_some()
+            || self.connect_timeout.is_some()
+            || self.pool_idle_timeout.is_some()
+            || self.pool_max_idle_per_host.is_some()
+            || !self.default_headers.is_empty()
+            || self.proxy.is_some()
+            || self.no_proxy
+            || self.user_agent.is_some()
+            || !self.root_certificates.is_empty()
+            || self.accept_invalid_certs
+    }
+
+    fn build_reqwest_client(self) -> Result<reqwest::Client, TradeApiError> {
+        let mut builder = reqwest::Client::builder();
+
+        for (name, timeout) in [("timeout", self.timeout), ("connect_timeout", self.connect_timeout)] {
+            if timeout.is_some_and(|timeout| timeout.is_zero()) {
+                return Err(invalid_config(format!("{} must be greater than zero", name)));
+            }
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.default_headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| invalid_config(format!("Invalid header name: {}", name)))?;
+            let header_value = HeaderValue::from_str(value)
+                .map_err(|_| invalid_config(format!("Invalid value for header {}", name)))?;
+            headers.insert(header_name, header_value);
+        }
+        builder = builder.default_headers(headers);
+
+        if let Some(user_agent) = &self.user_agent {
+            let user_agent = HeaderValue::from_str(user_agent)
+                .map_err(|_| invalid_config(format!("Invalid user agent: {}", user_agent)))?;
+            builder = builder.user_agent(user_agent);
+        }
+
+        if self.proxy.is_some() && self.no_proxy {
+            return Err(invalid_config("proxy and no_proxy are mutually exclusive".to_string()));
+        }
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy.as_str())
+                .map_err(|err| invalid_config(format!("Invalid proxy {}: {}", proxy, err)))?;
+            builder = builder.proxy(proxy);
+        }
+        if self.no_proxy {
+            builder = builder.no_proxy();
+        }
+
+        for certificate in self.root_certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+        builder = builder.danger_accept_invalid_certs(self.accept_invalid_certs);
+
+        builder.build().map_err(TradeApiError::Http)
+    }
+}
+
+fn invalid_config(message: String) -> TradeApiError {
+    TradeApiError::InvalidConfig(message)
+}
@@ -0,0 +1,61 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// Errors returned by the low level trade api client
+#[derive(Debug)]
+pub enum TradeApiError {
+    /// The request could not be sent or the response could not be read
+    Http(reqwest::Error),
+    /// The api responded with a non success status code
+    Status { status: u16, body: String },
+    /// The response body did not match the expected model
+    Json(serde_json::Error),
+    /// Reading or writing a fixture failed
+    Io(std::io::Error),
+    /// No recorded fixture exists for the request being replayed
+    MissingFixture(PathBuf),
+    /// The client was configured with invalid settings
+    InvalidConfig(String),
+}
+
+impl fmt::Display for TradeApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TradeApiError::Http(err) => write!(f, "HTTP error: {}", err),
+            TradeApiError::Status { status, body } => write!(f, "Unexpected status {}: {}", status, body),
+            TradeApiError::Json(err) => write!(f, "JSON error: {}", err),
+            TradeApiError::Io(err) => write!(f, "IO error: {}", err),
+            TradeApiError::MissingFixture(path) => write!(f, "Missing fixture: {}", path.display()),
+            TradeApiError::InvalidConfig(message) => write!(f, "Invalid configuration: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for TradeApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TradeApiError::Http(err) => Some(err),
+            TradeApiError::Json(err) => Some(err),
+            TradeApiError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for TradeApiError {
+    fn from(err: reqwest::Error) -> Self {
+        TradeApiError::Http(err)
+    }
+}
+
+impl From<serde_json::Error> for TradeApiError {
+    fn from(err: serde_json::Error) -> Self {
+        TradeApiError::Json(err)
+    }
+}
+
+impl From<std::io::Error> for TradeApiError {
+    fn from(err: std::io::Error) -> Self {
+        TradeApiError::Io(err)
+    }
+}
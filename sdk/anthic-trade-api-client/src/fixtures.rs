@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::error::TradeApiError;
+use crate::transport::*;
+
+/// Header carrying the api key, which is never written to a fixture
+pub const API_KEY_HEADER: &str = "ANTHIC-API-KEY";
+const REDACTED: &str = "<redacted>";
+
+/// A recorded request/response pair
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Fixture {
+    pub request: FixtureRequest,
+    pub response: FixtureResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct FixtureRequest {
+    pub method: String,
+    /// Path relative to the api base url, e.g. `/trade/info`
+    pub path: String,
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct FixtureResponse {
+    pub status: u16,
+    /// Response headers, e.g. `Retry-After`
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The response body exactly as received
+    pub body: String,
+}
+
+impl FixtureRequest {
+    /// Full urls are stored as their path, so that fixtures do not depend on the api host
+    pub fn new(method: &str, path: &str, headers: impl IntoIterator<Item = (String, String)>) -> Self {
+        Self {
+            method: method.to_string(),
+            path: relative_path(path).to_string(),
+            headers: redact(headers),
+        }
+    }
+}
+
+impl FixtureResponse {
+    pub fn new(status: u16, headers: impl IntoIterator<Item = (String, String)>, body: String) -> Self {
+        Self {
+            status,
+            headers: redact(headers),
+            body,
+        }
+    }
+}
+
+fn redact(headers: impl IntoIterator<Item = (String, String)>) -> BTreeMap<String, String> {
+    headers.into_iter()
+        .map(|(name, value)| {
+            if name.eq_ignore_ascii_case(API_KEY_HEADER) {
+                (name, REDACTED.to_string())
+            } else {
+                (name, value)
+            }
+        })
+        .collect()
+}
+
+/// The path of a full url, e.g. `/trade/info` for `https://host/trade/info`. Paths are returned as is.
+pub fn relative_path(path: &str) -> &str {
+    match path.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|start| &rest[start..]).unwrap_or("/"),
+        None => path,
+    }
+}
+
+/// The file name a request is stored under, e.g. `GET_trade_info.json`
+pub fn fixture_file_name(method: &str, path: &str) -> String {
+    let path: String = relative_path(path).trim_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    format!("{}_{}.json", method, path)
+}
+
+/// Writes every request/response pair to a directory
+#[derive(Debug, Clone)]
+pub struct FixtureRecorder {
+    dir: PathBuf,
+}
+
+impl FixtureRecorder {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn record(&self, fixture: &Fixture) -> Result<(), TradeApiError> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(fixture_file_name(&fixture.request.method, &fixture.request.path));
+        fs::write(path, serde_json::to_string_pretty(fixture)?)?;
+        Ok(())
+    }
+}
+
+/// Serves previously recorded responses from a directory
+#[derive(Debug, Clone)]
+pub struct FixtureReplay {
+    dir: PathBuf,
+}
+
+impl FixtureReplay {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn load(&self, method: &str, path: &str) -> Result<Fixture, TradeApiError> {
+        let file = self.dir.join(fixture_file_name(method, path));
+        if !file.exists() {
+            return Err(TradeApiError::MissingFixture(file));
+        }
+        let contents = fs::read_to_string(file)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+/// Wraps a transport and records every request/response pair it serves
+#[derive(Debug, Clone)]
+pub struct RecordingTransport<T> {
+    inner: T,
+    recorder: FixtureRecorder,
+}
+
+impl<T: TradeApiTransport> RecordingTransport<T> {
+    pub fn new(inner: T, recorder: FixtureRecorder) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+impl<T: TradeApiTransport> TradeApiTransport for RecordingTransport<T> {
+    async fn send(&self, request: TradeApiRequest) -> Result<TradeApiResponse, TradeApiError> {
+        let fixture_request = FixtureRequest::new(request.method.as_str(), &request.path, request.headers.clone());
+        let response = self.inner.send(request).await?;
+        self.recorder.record(&Fixture {
+            request: fixture_request,
+            response: FixtureResponse::new(response.status, response.headers.clone(), response.body.clone()),
+        })?;
+        Ok(response)
+    }
+}
+
+/// Serves recorded fixtures without network access
+#[derive(Debug, Clone)]
+pub struct ReplayTransport {
+    replay: FixtureReplay,
+}
+
+impl ReplayTransport {
+    pub fn new(replay: FixtureReplay) -> Self {
+        Self { replay }
+    }
+}
+
+impl TradeApiTransport for ReplayTransport {
+    async fn send(&self, request: TradeApiRequest) -> Result<TradeApiResponse, TradeApiError> {
+        let fixture = self.replay.load(request.method.as_str(), &request.path)?;
+        Ok(TradeApiResponse {
+            status: fixture.response.status,
+            headers: fixture.response.headers.into_iter().collect(),
+            body: fixture.response.body,
+        })
+    }
+}
@@ -1,110 +1,202 @@
+pub mod builder;
+pub mod error;
+pub mod fixtures;
 pub mod model;
+pub mod retry;
+pub mod transport;
 
+use std::path::PathBuf;
 use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::fixtures::*;
 use crate::model::*;
+use crate::retry::*;
+use crate::transport::*;
 
-pub struct AnthicTradeApiClient {
-    client: reqwest::Client,
-    url: String,
+pub use crate::builder::AnthicTradeApiClientBuilder;
+pub use crate::error::TradeApiError;
+
+pub struct AnthicTradeApiClient<T = ReqwestTransport> {
+    transport: T,
     api_key: String,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<RateLimiter>,
 }
 
 impl AnthicTradeApiClient {
     pub fn new(url: String, api_key: String) -> Self {
+        Self::with_transport(ReqwestTransport::new(url), api_key)
+    }
+
+    pub fn builder() -> AnthicTradeApiClientBuilder {
+        AnthicTradeApiClientBuilder::new()
+    }
+}
+
+impl AnthicTradeApiClient<RecordingTransport<ReqwestTransport>> {
+    /// A live client which additionally writes every request/response pair to `dir`.
+    /// The api key header is redacted in the written fixtures.
+    pub fn recording(url: String, api_key: String, dir: impl Into<PathBuf>) -> Self {
+        let transport = RecordingTransport::new(ReqwestTransport::new(url), FixtureRecorder::new(dir));
+        Self::with_transport(transport, api_key)
+    }
+}
+
+impl AnthicTradeApiClient<ReplayTransport> {
+    /// A client which never touches the network and serves responses previously recorded to `dir`
+    pub fn replaying(dir: impl Into<PathBuf>) -> Self {
+        Self::with_transport(ReplayTransport::new(FixtureReplay::new(dir)), String::new())
+    }
+}
+
+impl<T: TradeApiTransport> AnthicTradeApiClient<T> {
+    pub fn with_transport(transport: T, api_key: String) -> Self {
         Self {
-            client: reqwest::Client::new(),
-            url,
+            transport,
             api_key,
+            retry_policy: RetryPolicy::default(),
+            rate_limiter: None,
         }
     }
 
-    pub async fn network_status(&self) -> Result<NetworkStatusResponse, reqwest::Error> {
-        let url = format!("{}/network/status", &self.url);
-        let res = self.client.get(url).send().await?;
-        res.json().await
+    /// Fails if the policy does not pass [`RetryPolicy::validate`]
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Result<Self, TradeApiError> {
+        retry_policy.validate()?;
+        self.retry_policy = retry_policy;
+        Ok(self)
+    }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
     }
 
-    pub async fn info(&self) -> Result<InfoResponse, reqwest::Error> {
-        let url = format!("{}/trade/info", &self.url);
-        let res = self.client.get(url).send().await?;
-        res.json().await
+    pub async fn network_status(&self) -> Result<NetworkStatusResponse, TradeApiError> {
+        self.get_public("/network/status").await
+    }
+
+    pub async fn info(&self) -> Result<InfoResponse, TradeApiError> {
+        self.get_public("/trade/info").await
     }
 
     pub async fn account_address_info(
         &self,
         address: String,
-    ) -> Result<AccountAddressInfo, reqwest::Error> {
-        let url = format!("{}/trade/account_addresses/{}", &self.url, address);
-        let res = self.client.get(url).send().await?;
-        res.json().await
+    ) -> Result<AccountAddressInfo, TradeApiError> {
+        self.get_public(&format!("/trade/account_addresses/{}", address)).await
     }
 
-    pub async fn tokens(&self) -> Result<TokensResponse, reqwest::Error> {
-        let url = format!("{}/trade/tokens", &self.url);
-        let res = self.client.get(url).send().await?;
-        res.json().await
+    pub async fn tokens(&self) -> Result<TokensResponse, TradeApiError> {
+        self.get_public("/trade/tokens").await
     }
 
-    pub async fn token_pairs(&self) -> Result<TokenPairsResponse, reqwest::Error> {
-        let url = format!("{}/trade/token_pairs", &self.url);
-        let res = self.client.get(url).send().await?;
-        res.json().await
+    pub async fn token_pairs(&self) -> Result<TokenPairsResponse, TradeApiError> {
+        self.get_public("/trade/token_pairs").await
     }
 
-    pub async fn accounts(&self) -> Result<AccountsResponse, reqwest::Error> {
-        let url = format!("{}/trade/accounts", &self.url);
-        let res = self
-            .client
-            .get(url)
-            .header("ANTHIC-API-KEY", self.api_key.as_str())
-            .send()
-            .await?;
-        res.json().await
+    /// Submits a signed partial transaction. This is not idempotent and so only retried if the
+    /// retry policy allows non-idempotent retries.
+    pub async fn submit_order(&self, signed_partial_transaction_hex: String) -> Result<SubmitOrderResponse, TradeApiError> {
+        self.post("/trade/orders", &SubmitOrderRequest { signed_partial_transaction_hex }).await
     }
 
-    pub async fn instamint_info(&self) -> Result<InstamintInfo, reqwest::Error> {
-        let url = format!("{}/instamint/info", &self.url);
-        let res = self
-            .client
-            .get(url)
-            .send()
-            .await?;
-        res.json().await
+    pub async fn accounts(&self) -> Result<AccountsResponse, TradeApiError> {
+        self.get_authenticated("/trade/accounts").await
     }
 
-    pub async fn instamint_tokens(&self) -> Result<InstamintTokensResponse, reqwest::Error> {
-        let url = format!("{}/instamint/tokens", &self.url);
-        let res = self
-            .client
-            .get(url)
-            .send()
-            .await?;
-        res.json().await
+    pub async fn instamint_info(&self) -> Result<InstamintInfo, TradeApiError> {
+        self.get_public("/instamint/info").await
     }
 
-    pub async fn instamint_account(&self) -> Result<InstamintAccountResponse, reqwest::Error> {
-        self.get(format!("{}/instamint/account", &self.url)).await
+    pub async fn instamint_tokens(&self) -> Result<InstamintTokensResponse, TradeApiError> {
+        self.get_public("/instamint/tokens").await
     }
 
-    pub async fn instamint_allowance(&self) -> Result<InstamintAllowance, reqwest::Error> {
-        self.get(format!("{}/instamint/account/allowance", &self.url)).await
+    pub async fn instamint_account(&self) -> Result<InstamintAccountResponse, TradeApiError> {
+        self.get_authenticated("/instamint/account").await
     }
 
-    pub async fn instamint_balance(&self) -> Result<InstamintBalance, reqwest::Error> {
-        self.get(format!("{}/instamint/account/balance", &self.url)).await
+    pub async fn instamint_allowance(&self) -> Result<InstamintAllowance, TradeApiError> {
+        self.get_authenticated("/instamint/account/allowance").await
     }
 
-    pub async fn instamint_payback_addresses(&self) -> Result<InstamintPaybackAddresses, reqwest::Error> {
-        self.get(format!("{}/instamint/account/payback-addresses", &self.url)).await
+    pub async fn instamint_balance(&self) -> Result<InstamintBalance, TradeApiError> {
+        self.get_authenticated("/instamint/account/balance").await
     }
 
-    pub async fn get<T: DeserializeOwned>(&self, url: String) -> Result<T, reqwest::Error> {
-        let res = self
-            .client
-            .get(url)
-            .header("ANTHIC-API-KEY", self.api_key.as_str())
-            .send()
-            .await?;
-        res.json().await
+    pub async fn instamint_payback_addresses(&self) -> Result<InstamintPaybackAddresses, TradeApiError> {
+        self.get_authenticated("/instamint/account/payback-addresses").await
+    }
+
+    /// Authenticated GET of a full url, e.g. `https://.../trade/accounts`. Paths relative to the
+    /// api base url, e.g. `/trade/accounts`, are also accepted.
+    pub async fn get<R: DeserializeOwned>(&self, url: String) -> Result<R, TradeApiError> {
+        self.request(&url, true).await
+    }
+
+    /// Authenticated POST of a JSON body. POST requests are only retried if the retry policy allows
+    /// non-idempotent retries.
+    pub async fn post<B: Serialize, R: DeserializeOwned>(&self, path: &str, body: &B) -> Result<R, TradeApiError> {
+        let request = TradeApiRequest::post(path, serde_json::to_string(body)?)
+            .header(API_KEY_HEADER, self.api_key.as_str());
+        let response = self.send(request).await?;
+        Ok(serde_json::from_str(&response.body)?)
+    }
+
+    async fn get_authenticated<R: DeserializeOwned>(&self, path: &str) -> Result<R, TradeApiError> {
+        self.request(path, true).await
+    }
+
+    async fn get_public<R: DeserializeOwned>(&self, path: &str) -> Result<R, TradeApiError> {
+        self.request(path, false).await
+    }
+
+    async fn request<R: DeserializeOwned>(&self, path: &str, authenticated: bool) -> Result<R, TradeApiError> {
+        let mut request = TradeApiRequest::get(path);
+        if authenticated {
+            request = request.header(API_KEY_HEADER, self.api_key.as_str());
+        }
+
+        let response = self.send(request).await?;
+        Ok(serde_json::from_str(&response.body)?)
+    }
+
+    /// Sends a request, applying the rate limiter and retry policy
+    async fn send(&self, request: TradeApiRequest) -> Result<TradeApiResponse, TradeApiError> {
+        let mut attempt = 0;
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire().await;
+            }
+
+            let (error, retry_after) = match self.transport.send(request.clone()).await {
+                Ok(response) if response.is_success() => return Ok(response),
+                Ok(response) => {
+                    let retry_after = response.header("Retry-After").and_then(parse_retry_after);
+                    let error = TradeApiError::Status {
+                        status: response.status,
+                        body: response.body,
+                    };
+                    (error, retry_after)
+                }
+                Err(error) => (error, None),
+            };
+
+            match self.retry_policy.retry_delay(request.method, attempt, &error, retry_after) {
+                Some(delay) => {
+                    attempt += 1;
+                    tokio::time::sleep(delay).await;
+                }
+                None => return Err(error),
+            }
+        }
     }
 }
@@ -25,6 +25,21 @@ pub struct AnthicLevelFee {
     pub maker_fee: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct SubmitOrderRequest {
+    /// Hex encoded raw `SignedPartialTransactionV2`
+    pub signed_partial_transaction_hex: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct SubmitOrderResponse {
+    /// Bech32 encoded subintent hash
+    pub subintent_hash: String,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
 #[derive(Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct AccountAddressInfo {
     pub level: u64,
@@ -92,6 +107,9 @@ pub struct InstamintToken {
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct InstamintAccountResponse {
     pub account: InstamintAccount,
+    /// Every instamint account managed by the api key, when there are several
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<InstamintAccount>,
 }
 
 #[derive(Default, Clone, Serialize, Deserialize)]
@@ -132,4 +150,11 @@ pub struct TokenAmount {
 pub struct TokenDefinition {
     pub resource_address: String,
     pub symbol: String,
+    /// Optional, a missing divisibility is treated as unknown rather than assumed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub divisibility: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
 }
@@ -0,0 +1,175 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use rand::Rng;
+use crate::error::TradeApiError;
+use crate::transport::HttpMethod;
+
+/// Decides whether and when a failed request is retried.
+///
+/// Only idempotent (GET) requests are retried unless `retry_non_idempotent` is set.
+/// Policies are checked with [`Self::validate`] when given to a client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the initial attempt, zero disables retries
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Fraction of each backoff which is randomised, between 0.0 and 1.0
+    pub jitter: f64,
+    /// A `Retry-After` longer than this fails the request instead of waiting
+    pub max_retry_after: Duration,
+    pub retryable_statuses: Vec<u16>,
+    /// Also retry requests which are not idempotent, e.g. order submission
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.5,
+            max_retry_after: Duration::from_secs(60),
+            retryable_statuses: vec![429, 502, 503, 504],
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Checks that backoffs can be computed: the multiplier is finite and at least 1,
+    /// the jitter within 0..=1, and the initial backoff no longer than the maximum
+    pub fn validate(&self) -> Result<(), TradeApiError> {
+        if !self.multiplier.is_finite() || self.multiplier < 1.0 {
+            return Err(TradeApiError::InvalidConfig(format!("Retry multiplier must be at least 1, got {}", self.multiplier)));
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err(TradeApiError::InvalidConfig(format!("Retry jitter must be within 0..=1, got {}", self.jitter)));
+        }
+        if self.initial_backoff > self.max_backoff {
+            return Err(TradeApiError::InvalidConfig("Initial backoff must not exceed the maximum backoff".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The delay before retrying a request which failed on the given zero-based attempt,
+    /// or `None` if the error should be returned
+    pub fn retry_delay(
+        &self,
+        method: HttpMethod,
+        attempt: u32,
+        error: &TradeApiError,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+        if method == HttpMethod::Post && !self.retry_non_idempotent {
+            return None;
+        }
+
+        let retryable = match error {
+            // Other request errors may have reached the api, or will fail again
+            TradeApiError::Http(err) => err.is_connect() || err.is_timeout(),
+            TradeApiError::Status { status, .. } => self.retryable_statuses.contains(status),
+            _ => false,
+        };
+        if !retryable {
+            return None;
+        }
+
+        match retry_after {
+            Some(retry_after) if retry_after > self.max_retry_after => None,
+            Some(retry_after) => Some(retry_after),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    /// Exponential backoff with jitter for the given zero-based attempt
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self.initial_backoff.as_secs_f64() * self.multiplier.powi(i32::try_from(attempt).unwrap_or(i32::MAX));
+        let capped = exponential.min(self.max_backoff.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = 1.0 - jitter * rand::thread_rng().gen::<f64>();
+        Duration::try_from_secs_f64(capped * factor).unwrap_or(self.max_backoff)
+    }
+}
+
+/// Parses a `Retry-After` header, given either in seconds or as an HTTP date
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// Client side token bucket limiting the rate of requests sent
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    state: Mutex<TokenBucket>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Allows `requests_per_second` on average, with bursts of up to `burst` requests
+    pub fn new(requests_per_second: f64, burst: u32) -> Result<Self, TradeApiError> {
+        if !requests_per_second.is_finite() || requests_per_second <= 0.0 {
+            return Err(TradeApiError::InvalidConfig(format!("Rate limit must be positive, got {}", requests_per_second)));
+        }
+        let capacity = f64::from(burst.max(1));
+        Ok(Self {
+            capacity,
+            refill_per_sec: requests_per_second,
+            state: Mutex::new(TokenBucket {
+                tokens: capacity,
+                last_refill: Instant::now(),
+            }),
+        })
+    }
+
+    /// Waits until a request may be sent
+    pub async fn acquire(&self) {
+        loop {
+            match self.try_acquire() {
+                Ok(()) => return,
+                Err(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+
+    /// Takes a token if one is available, otherwise returns how long until one is
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        let mut bucket = self.state.lock().unwrap();
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let missing = 1.0 - bucket.tokens;
+            Err(Duration::from_secs_f64(missing / self.refill_per_sec))
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::Mutex;
+use serde::Serialize;
+use crate::error::TradeApiError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HttpMethod {
+    Get,
+    Post,
+}
+
+impl HttpMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+        }
+    }
+}
+
+/// A request to the trade api. The path is relative to the api base url, e.g. `/trade/info`,
+/// or a full url.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeApiRequest {
+    pub method: HttpMethod,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl TradeApiRequest {
+    pub fn get(path: impl Into<String>) -> Self {
+        Self {
+            method: HttpMethod::Get,
+            path: path.into(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    pub fn post(path: impl Into<String>, body: String) -> Self {
+        Self {
+            method: HttpMethod::Post,
+            path: path.into(),
+            headers: Vec::new(),
+            body: Some(body),
+        }
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeApiResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl TradeApiResponse {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Sends requests to the trade api. Implement this to plug in a custom HTTP stack or to instrument requests.
+pub trait TradeApiTransport: Send + Sync {
+    fn send(&self, request: TradeApiRequest) -> impl Future<Output = Result<TradeApiResponse, TradeApiError>> + Send;
+}
+
+/// The default transport, backed by a `reqwest::Client`
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl ReqwestTransport {
+    pub fn new(url: String) -> Self {
+        Self::with_client(reqwest::Client::new(), url)
+    }
+
+    pub fn with_client(client: reqwest::Client, url: String) -> Self {
+        Self { client, url }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl TradeApiTransport for ReqwestTransport {
+    async fn send(&self, request: TradeApiRequest) -> Result<TradeApiResponse, TradeApiError> {
+        let url = if request.path.starts_with("http://") || request.path.starts_with("https://") {
+            request.path.clone()
+        } else {
+            format!("{}{}", &self.url, request.path)
+        };
+        let mut builder = match request.method {
+            HttpMethod::Get => self.client.get(url),
+            HttpMethod::Post => self.client.post(url),
+        };
+        for (name, value) in &request.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        if let Some(body) = request.body {
+            builder = builder
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body);
+        }
+
+        let res = builder.send().await?;
+        let status = res.status().as_u16();
+        let headers = res.headers().iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = res.text().await?;
+
+        Ok(TradeApiResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// A transport serving canned responses from memory, for tests.
+///
+/// Responses registered for the same route are served in order, the last one being repeated.
+/// Unknown routes respond with a 404.
+#[derive(Debug, Default)]
+pub struct InMemoryTransport {
+    responses: Mutex<HashMap<(HttpMethod, String), VecDeque<TradeApiResponse>>>,
+    requests: Mutex<Vec<TradeApiRequest>>,
+}
+
+impl InMemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn respond(self, method: HttpMethod, path: &str, response: TradeApiResponse) -> Self {
+        self.responses.lock().unwrap()
+            .entry((method, path.to_string()))
+            .or_default()
+            .push_back(response);
+        self
+    }
+
+    pub fn respond_json<V: Serialize>(self, method: HttpMethod, path: &str, value: &V) -> Self {
+        let body = serde_json::to_string(value).unwrap();
+        self.respond(method, path, TradeApiResponse::new(200, body))
+    }
+
+    /// All requests sent so far, in order
+    pub fn requests(&self) -> Vec<TradeApiRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl TradeApiTransport for InMemoryTransport {
+    async fn send(&self, request: TradeApiRequest) -> Result<TradeApiResponse, TradeApiError> {
+        let response = {
+            let mut responses = self.responses.lock().unwrap();
+            match responses.get_mut(&(request.method, request.path.clone())) {
+                Some(queue) if queue.len() > 1 => queue.pop_front(),
+                Some(queue) => queue.front().cloned(),
+                None => None,
+            }
+        };
+        self.requests.lock().unwrap().push(request);
+
+        Ok(response.unwrap_or_else(|| TradeApiResponse::new(404, "")))
+    }
+}
@@ -0,0 +1,93 @@
+use std::fs;
+use std::path::PathBuf;
+use anthic_trade_api_client::fixtures::*;
+use anthic_trade_api_client::model::AccountsResponse;
+use anthic_trade_api_client::transport::*;
+use anthic_trade_api_client::AnthicTradeApiClient;
+
+const API_KEY: &str = "secret-api-key";
+
+fn fixture_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("anthic-recording-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn recording_client(transport: InMemoryTransport, dir: &PathBuf) -> AnthicTradeApiClient<RecordingTransport<InMemoryTransport>> {
+    AnthicTradeApiClient::with_transport(RecordingTransport::new(transport, FixtureRecorder::new(dir)), API_KEY.to_string())
+}
+
+#[tokio::test]
+async fn api_key_is_never_written() {
+    let dir = fixture_dir("api-key");
+    let transport = InMemoryTransport::new()
+        .respond_json(HttpMethod::Get, "/trade/accounts", &AccountsResponse::default());
+    recording_client(transport, &dir).accounts().await.unwrap();
+
+    let files: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+    assert_eq!(files.len(), 1);
+    for file in files {
+        let contents = fs::read_to_string(file).unwrap();
+        assert!(!contents.contains(API_KEY));
+        assert!(contents.contains("<redacted>"));
+    }
+}
+
+#[tokio::test]
+async fn bodies_and_headers_replay_as_received() {
+    let dir = fixture_dir("raw");
+    let body = r#"{"accounts":[],"precise":0.100000000000000000000000001}"#;
+    let mut response = TradeApiResponse::new(200, body);
+    response.headers.push(("Retry-After".to_string(), "3".to_string()));
+    let transport = InMemoryTransport::new()
+        .respond(HttpMethod::Get, "/trade/accounts", response.clone());
+    recording_client(transport, &dir).accounts().await.unwrap();
+
+    let replay = ReplayTransport::new(FixtureReplay::new(&dir));
+    let replayed = replay.send(TradeApiRequest::get("/trade/accounts")).await.unwrap();
+    assert_eq!(replayed.body, body);
+    assert_eq!(replayed.header("retry-after"), Some("3"));
+}
+
+#[tokio::test]
+async fn string_bodies_are_not_unquoted() {
+    let dir = fixture_dir("string");
+    let transport = InMemoryTransport::new()
+        .respond(HttpMethod::Get, "/trade/accounts", TradeApiResponse::new(500, r#""abc""#));
+    assert!(recording_client(transport, &dir).accounts().await.is_err());
+
+    let fixture = FixtureReplay::new(&dir).load("GET", "/trade/accounts").unwrap();
+    assert_eq!(fixture.response.body, r#""abc""#);
+}
+
+#[test]
+fn fixtures_are_named_by_path() {
+    assert_eq!(fixture_file_name("GET", "https://trade-api.staging.anthic.io/trade/info"), "GET_trade_info.json");
+    assert_eq!(fixture_file_name("GET", "/trade/info"), "GET_trade_info.json");
+    assert_eq!(relative_path("https://trade-api.staging.anthic.io"), "/");
+}
+
+/// Re-records the fixtures replayed by `tests/replay.rs` against staging:
+/// `ANTHIC_API_KEY=... cargo test -p anthic-trade-api-client --test recording -- --ignored`
+#[tokio::test]
+#[ignore]
+async fn record_staging_fixtures() {
+    let url = std::env::var("ANTHIC_TRADE_API_URL").unwrap_or("https://trade-api.staging.anthic.io".to_string());
+    let api_key = std::env::var("ANTHIC_API_KEY").expect("ANTHIC_API_KEY must be set to record fixtures");
+    let client = AnthicTradeApiClient::recording(url, api_key, concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"));
+
+    client.network_status().await.unwrap();
+    client.info().await.unwrap();
+    client.tokens().await.unwrap();
+    client.token_pairs().await.unwrap();
+    let accounts = client.accounts().await.unwrap();
+    if let Some(account) = accounts.accounts.first() {
+        client.account_address_info(account.address.clone()).await.unwrap();
+    }
+    client.instamint_info().await.unwrap();
+    client.instamint_tokens().await.unwrap();
+    client.instamint_account().await.unwrap();
+    client.instamint_allowance().await.unwrap();
+    client.instamint_balance().await.unwrap();
+    client.instamint_payback_addresses().await.unwrap();
+}
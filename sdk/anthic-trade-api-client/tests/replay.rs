@@ -0,0 +1,109 @@
+use anthic_trade_api_client::fixtures::ReplayTransport;
+use anthic_trade_api_client::model::NetworkStatusResponse;
+use anthic_trade_api_client::{AnthicTradeApiClient, TradeApiError};
+
+const ACCOUNT: &str = "account_tdx_2_1c8mqe0s0nwjlhtc8qmjl6ylqgvs0yqkq4a5ly4dcpakqtgpw27t3s3";
+
+fn client() -> AnthicTradeApiClient<ReplayTransport> {
+    AnthicTradeApiClient::replaying(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+}
+
+#[tokio::test]
+async fn replays_network_status() {
+    let status = client().network_status().await.unwrap();
+    assert_eq!(status.cur_epoch, 71234);
+}
+
+#[tokio::test]
+async fn replays_info() {
+    let info = client().info().await.unwrap();
+    assert_eq!(info.verify_parent_access_rule_sbor_hex, "5c220000");
+    assert_eq!(info.per_token_settlement_fee.len(), 2);
+    assert_eq!(info.per_token_settlement_fee[0].symbol, "xUSDC");
+    assert_eq!(info.per_level_anthic_fee[1].taker_fee, "0.0008");
+}
+
+#[tokio::test]
+async fn replays_account_address_info() {
+    let info = client().account_address_info(ACCOUNT.to_string()).await.unwrap();
+    assert_eq!(info.level, 1);
+}
+
+#[tokio::test]
+async fn replays_tokens() {
+    let tokens = client().tokens().await.unwrap().tokens;
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens[0].symbol, "XRD");
+    assert_eq!(tokens[0].divisibility, Some(18));
+    assert_eq!(tokens[1].name, None);
+}
+
+#[tokio::test]
+async fn replays_token_pairs() {
+    let pairs = client().token_pairs().await.unwrap().token_pairs;
+    assert_eq!(pairs.len(), 2);
+    assert_eq!(pairs[0].base, "xwBTC");
+    assert_eq!(pairs[0].quote, "xUSDC");
+}
+
+#[tokio::test]
+async fn replays_accounts() {
+    let accounts = client().accounts().await.unwrap().accounts;
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].address, ACCOUNT);
+    assert_eq!(accounts[0].balances[0].amount, "1250.5");
+}
+
+#[tokio::test]
+async fn replays_instamint_info() {
+    let info = client().instamint_info().await.unwrap();
+    assert!(info.instamint_component.starts_with("component_tdx_2_1"));
+    assert!(info.customer_badge_resource.starts_with("resource_tdx_2_1"));
+}
+
+#[tokio::test]
+async fn replays_instamint_tokens() {
+    let tokens = client().instamint_tokens().await.unwrap().tokens;
+    assert_eq!(tokens[0].symbol, "xUSDC");
+    assert_eq!(tokens[0].repayment_tokens[0].chain, "ethereum");
+    assert_eq!(tokens[0].repayment_tokens[0].testnet.as_deref(), Some("false"));
+}
+
+#[tokio::test]
+async fn replays_instamint_account() {
+    let response = client().instamint_account().await.unwrap();
+    assert_eq!(response.account.address, ACCOUNT);
+    assert_eq!(response.account.customer_badge_non_fungible_local_ids, vec!["5cc0010000000000000001".to_string()]);
+}
+
+#[tokio::test]
+async fn replays_instamint_allowance() {
+    let allowance = client().instamint_allowance().await.unwrap();
+    assert_eq!(allowance.allowance, "10000");
+}
+
+#[tokio::test]
+async fn replays_instamint_balance() {
+    let balance = client().instamint_balance().await.unwrap();
+    assert_eq!(balance.balances[0].symbol, "xUSDC");
+    assert_eq!(balance.balances[0].amount, "250");
+}
+
+#[tokio::test]
+async fn replays_instamint_payback_addresses() {
+    let addresses = client().instamint_payback_addresses().await.unwrap().payback_addresses;
+    assert_eq!(addresses[0].chain, "ethereum");
+    assert_eq!(addresses[0].address, "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+}
+
+#[tokio::test]
+async fn get_accepts_paths() {
+    let status: NetworkStatusResponse = client().get("/network/status".to_string()).await.unwrap();
+    assert_eq!(status.cur_epoch, 71234);
+}
+
+#[tokio::test]
+async fn missing_fixture_is_an_error() {
+    let result = client().account_address_info("account_unknown".to_string()).await;
+    assert!(matches!(result, Err(TradeApiError::MissingFixture(_))));
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+use anthic_trade_api_client::retry::*;
+use anthic_trade_api_client::transport::*;
+use anthic_trade_api_client::{AnthicTradeApiClient, TradeApiError};
+
+fn policy() -> RetryPolicy {
+    RetryPolicy {
+        max_retries: 3,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(4),
+        ..Default::default()
+    }
+}
+
+fn status(status: u16) -> TradeApiError {
+    TradeApiError::Status { status, body: String::new() }
+}
+
+#[test]
+fn backoff_grows_exponentially_up_to_the_maximum() {
+    let policy = RetryPolicy {
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_secs(1),
+        multiplier: 2.0,
+        jitter: 0.0,
+        ..Default::default()
+    };
+    let backoffs: Vec<_> = (0..6).map(|attempt| policy.backoff(attempt).as_millis()).collect();
+    assert_eq!(backoffs, vec![100, 200, 400, 800, 1000, 1000]);
+    assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(1));
+}
+
+#[test]
+fn jitter_only_shortens_the_backoff() {
+    let policy = RetryPolicy {
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_secs(1),
+        multiplier: 2.0,
+        jitter: 0.5,
+        ..Default::default()
+    };
+    for attempt in 0..8 {
+        let expected = Duration::from_millis(100 * 2u64.pow(attempt)).min(Duration::from_secs(1));
+        for _ in 0..20 {
+            let backoff = policy.backoff(attempt);
+            assert!(backoff <= expected, "{:?} > {:?}", backoff, expected);
+            assert!(backoff >= expected / 2, "{:?} < {:?}", backoff, expected / 2);
+        }
+    }
+}
+
+#[test]
+fn invalid_policies_are_rejected() {
+    let invalid = [
+        RetryPolicy { multiplier: f64::NAN, ..Default::default() },
+        RetryPolicy { multiplier: f64::INFINITY, ..Default::default() },
+        RetryPolicy { multiplier: 0.5, ..Default::default() },
+        RetryPolicy { multiplier: -2.0, ..Default::default() },
+        RetryPolicy { jitter: f64::NAN, ..Default::default() },
+        RetryPolicy { jitter: -0.1, ..Default::default() },
+        RetryPolicy { jitter: 1.5, ..Default::default() },
+        RetryPolicy { initial_backoff: Duration::from_secs(10), max_backoff: Duration::from_secs(1), ..Default::default() },
+    ];
+    for policy in invalid {
+        assert!(matches!(policy.validate(), Err(TradeApiError::InvalidConfig(_))), "{:?}", policy);
+        assert!(AnthicTradeApiClient::with_transport(InMemoryTransport::new(), String::new()).with_retry_policy(policy.clone()).is_err());
+        assert!(AnthicTradeApiClient::builder().url("http://localhost").retry_policy(policy).build().is_err());
+    }
+    assert!(RetryPolicy::default().validate().is_ok());
+    assert!(RetryPolicy::none().validate().is_ok());
+}
+
+#[test]
+fn retryable_statuses_are_retried_for_gets_only() {
+    let policy = policy();
+    assert!(policy.retry_delay(HttpMethod::Get, 0, &status(503), None).is_some());
+    assert!(policy.retry_delay(HttpMethod::Get, 0, &status(429), None).is_some());
+    assert!(policy.retry_delay(HttpMethod::Get, 0, &status(400), None).is_none());
+    assert!(policy.retry_delay(HttpMethod::Get, 0, &status(500), None).is_none());
+    assert!(policy.retry_delay(HttpMethod::Post, 0, &status(503), None).is_none());
+
+    let retry_posts = RetryPolicy { retry_non_idempotent: true, ..policy.clone() };
+    assert!(retry_posts.retry_delay(HttpMethod::Post, 0, &status(503), None).is_some());
+}
+
+#[test]
+fn other_errors_are_not_retried() {
+    let policy = policy();
+    let json = TradeApiError::Json(serde_json::from_str::<u32>("not json").unwrap_err());
+    assert!(policy.retry_delay(HttpMethod::Get, 0, &json, None).is_none());
+    assert!(policy.retry_delay(HttpMethod::Get, 0, &TradeApiError::InvalidConfig(String::new()), None).is_none());
+}
+
+#[test]
+fn retries_stop_after_max_retries() {
+    let policy = policy();
+    assert!(policy.retry_delay(HttpMethod::Get, 2, &status(503), None).is_some());
+    assert!(policy.retry_delay(HttpMethod::Get, 3, &status(503), None).is_none());
+    assert!(RetryPolicy::none().retry_delay(HttpMethod::Get, 0, &status(503), None).is_none());
+}
+
+#[test]
+fn retry_after_is_honoured_up_to_its_maximum() {
+    let policy = policy();
+    let retry_after = Duration::from_secs(7);
+    assert_eq!(policy.retry_delay(HttpMethod::Get, 0, &status(429), Some(retry_after)), Some(retry_after));
+    assert_eq!(policy.retry_delay(HttpMethod::Get, 0, &status(429), Some(Duration::from_secs(61))), None);
+    assert_eq!(parse_retry_after(" 3 "), Some(Duration::from_secs(3)));
+    assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+    assert_eq!(parse_retry_after("soon"), None);
+}
+
+#[tokio::test]
+async fn connection_errors_are_retried() {
+    // Nothing listens on port 1
+    let error = reqwest::get("http://127.0.0.1:1").await.unwrap_err();
+    assert!(error.is_connect());
+    assert!(policy().retry_delay(HttpMethod::Get, 0, &TradeApiError::Http(error), None).is_some());
+}
+
+#[tokio::test]
+async fn client_retries_transient_statuses() {
+    let transport = InMemoryTransport::new()
+        .respond(HttpMethod::Get, "/network/status", TradeApiResponse::new(503, ""))
+        .respond(HttpMethod::Get, "/network/status", TradeApiResponse::new(502, ""))
+        .respond(HttpMethod::Get, "/network/status", TradeApiResponse::new(200, "{}"))
+        .respond(HttpMethod::Post, "/trade/orders", TradeApiResponse::new(503, ""));
+    let client = AnthicTradeApiClient::with_transport(transport, "api-key".to_string())
+        .with_retry_policy(policy())
+        .unwrap();
+
+    let _ = client.network_status().await;
+    assert!(client.post::<_, serde_json::Value>("/trade/orders", &serde_json::json!({})).await.is_err());
+
+    let paths: Vec<_> = client.transport().requests().into_iter().map(|request| request.path).collect();
+    assert_eq!(paths, vec!["/network/status", "/network/status", "/network/status", "/trade/orders"]);
+}
+
+#[test]
+fn rate_limiter_allows_bursts_then_waits() {
+    let rate_limiter = RateLimiter::new(10.0, 2).unwrap();
+    assert!(rate_limiter.try_acquire().is_ok());
+    assert!(rate_limiter.try_acquire().is_ok());
+    let wait = rate_limiter.try_acquire().unwrap_err();
+    assert!(wait > Duration::ZERO && wait <= Duration::from_millis(100), "{:?}", wait);
+}
+
+#[tokio::test]
+async fn rate_limiter_refills_over_time() {
+    let rate_limiter = RateLimiter::new(100.0, 1).unwrap();
+    rate_limiter.acquire().await;
+    assert!(rate_limiter.try_acquire().is_err());
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(rate_limiter.try_acquire().is_ok());
+}
+
+#[test]
+fn rate_limiter_rejects_invalid_rates() {
+    for requests_per_second in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        assert!(matches!(RateLimiter::new(requests_per_second, 1), Err(TradeApiError::InvalidConfig(_))));
+        assert!(AnthicTradeApiClient::builder().url("http://localhost").rate_limit(requests_per_second, 1).build().is_err());
+    }
+}